@@ -4,17 +4,29 @@ use winit::{
   window::{Fullscreen, WindowBuilder},
 };
 
-pub use self::physics::game_object::{GameObject, Transform};
+pub use self::game_object::GameObject;
+pub use self::physics::transform::Transform;
 pub use camera::Camera;
 pub use game_state::GameState;
+pub use profiler::{CpuSection, Profiler};
 pub use render::color::Color;
+pub use render::light::Light;
+pub use render::material::{Material, TextureMapping};
+pub use render::mesh::Shape3d;
+pub use render::mesh_4d::{Object4d, Rotation4d, Shape4d, View4d};
+pub use render::outline::{Highlight, OutlineStyle};
+pub use render::render_queue::FrameStats;
+pub use render::sky::{Sky, SkyGradient};
 pub use render::ui::TextObject;
 pub use time::Time;
 
 pub mod camera;
+mod game_object;
 mod game_state;
 pub mod physics;
+pub mod profiler;
 pub mod render;
+pub mod settings;
 mod time;
 
 const TITLE: &'static str = "Super Project Yourself At Unreasonably High Velocities Across Vast Distances Over Solid Color Abstract Shapes To Bring A Strange Creature To An Unknown Position In The Void 3D 64 (SPYAUHVAVDOSCASTBASCTAUPITV 3D64)";
@@ -22,6 +34,7 @@ const TITLE: &'static str = "Super Project Yourself At Unreasonably High Velocit
 pub trait Scene {
   fn start(&mut self, game: &mut GameState);
   fn update(&mut self, game: &mut GameState, input: &physics::input::Input, time: &Time);
+  fn on_trigger(&mut self, _game: &mut GameState, _event: &physics::collision::TriggerEvent) {}
   fn get_objects(&mut self) -> (Vec<&mut GameObject>, Vec<&TextObject>);
 }
 
@@ -70,18 +83,26 @@ pub async fn run(mut game: impl Scene + 'static) {
       },
       Event::RedrawRequested(window_id) if window_id == render_state.window().id() => {
         time.update();
+        game_state.profiler.begin_frame(time.delta_time);
 
         physics_state.input.update();
-        game.update(&mut game_state, &physics_state.input, &time);
+        game_state.profiler.lap(CpuSection::Input);
 
+        game.update(&mut game_state, &physics_state.input, &time);
+        // after the update so the events are about where things are this frame
+        game_state.collision.update_triggers();
+        for event in game_state.collision.drain_trigger_events() {
+          game.on_trigger(&mut game_state, &event);
+        }
         let (game_objects, text_objects) = game.get_objects();
+        let physics_time = game_state.collision.take_elapsed();
+        game_state
+          .profiler
+          .record_within(CpuSection::Physics, physics_time);
+        game_state.profiler.lap(CpuSection::Update);
+
         render_state.update_clear_color(&game_state.background_color);
-        render_state.update(
-          &game_state.camera,
-          time.delta_time,
-          game_objects,
-          text_objects,
-        );
+        render_state.update(&game_state, game_objects, text_objects);
 
         match render_state.render() {
           Ok(_) => {}
@@ -89,6 +110,16 @@ pub async fn run(mut game: impl Scene + 'static) {
           Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
           Err(e) => eprintln!("{:?}", e),
         }
+        game_state
+          .profiler
+          .record_within(CpuSection::SurfaceWait, render_state.surface_wait());
+        game_state.profiler.lap(CpuSection::Render);
+        game_state.frame_stats = render_state.frame_stats();
+        game_state.profiler.record_gpu(render_state.gpu_timings());
+
+        if physics_state.input.key_pressed(VirtualKeyCode::F12) {
+          render_state.request_screenshot();
+        }
 
         if physics_state.input.key_pressed(VirtualKeyCode::Escape) {
           if let Some(_) = render_state.window().fullscreen() {
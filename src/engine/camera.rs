@@ -13,6 +13,10 @@ pub struct Camera {
   pub position: Point3<f32>,
   pub yaw: Rad<f32>,
   pub pitch: Rad<f32>,
+  /// vertical field of view, copied into the `Projection` every frame
+  pub fovy: Rad<f32>,
+  /// position along the fourth axis, sliced 4D objects are cut by the hyperplane at this w
+  pub w: f32,
 }
 
 impl Camera {
@@ -25,6 +29,8 @@ impl Camera {
       position: position.into(),
       yaw: yaw.into(),
       pitch: pitch.into(),
+      fovy: Deg(60.0).into(),
+      w: 0.0,
     }
   }
 
@@ -61,6 +67,14 @@ impl Projection {
     self.aspect = width as f32 / height as f32;
   }
 
+  pub fn set_fovy<F: Into<Rad<f32>>>(&mut self, fovy: F) {
+    self.fovy = fovy.into();
+  }
+
+  pub fn set_zfar(&mut self, zfar: f32) {
+    self.zfar = zfar;
+  }
+
   pub fn calc_matrix(&self) -> Matrix4<f32> {
     OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
   }
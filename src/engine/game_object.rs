@@ -0,0 +1,50 @@
+use ncollide3d::pipeline::CollisionObjectSlabHandle;
+
+use super::{
+  physics::{
+    collision::{Collision, CollisionEvent, Tag},
+    transform::Transform,
+  },
+  render::{material::Material, mesh::Shape3d, outline::Highlight},
+};
+
+/// An object in the scene, physics only sees its transform and collider while the renderer
+/// reads its shape, material and highlight.
+pub struct GameObject {
+  pub transform: Transform,
+  pub shape: Shape3d,
+  pub material: Material,
+  pub highlight: Highlight,
+  pub collision_handle: CollisionObjectSlabHandle,
+  pub tag: Tag,
+  pub collision: CollisionEvent,
+}
+
+impl GameObject {
+  pub fn new(
+    pos: (f32, f32, f32),
+    rot: (f32, f32, f32),
+    sca: (f32, f32, f32),
+    color: [f32; 3],
+    tag: Tag,
+  ) -> Self {
+    let transform = Transform {
+      position: cgmath::Vector3::<f32>::new(pos.0, pos.1, pos.2),
+      rotation: cgmath::Vector3::<f32>::new(rot.0, rot.1, rot.2),
+      scale: cgmath::Vector3::<f32>::new(sca.0, sca.1, sca.2),
+    };
+    Self {
+      transform,
+      collision_handle: CollisionObjectSlabHandle(0),
+      shape: Shape3d::Cube,
+      material: Material::new(color),
+      highlight: Highlight::None,
+      tag,
+      collision: CollisionEvent::none(),
+    }
+  }
+
+  pub fn register_collision(&mut self, collision: &mut Collision) {
+    self.collision_handle = collision.add_collider(&self.transform, &self.tag);
+  }
+}
@@ -1,10 +1,33 @@
-use super::{camera, physics::collision::Collision, render::color::Color, GameObject};
+use super::{
+  camera,
+  physics::collision::Collision,
+  profiler::Profiler,
+  render::{
+    color::Color, light::Light, mesh_4d::Object4d, outline::OutlineStyle,
+    post_process::ScreenEffects, render_queue::FrameStats, shadow::BlobShadow, sky::Sky,
+  },
+  settings::Settings,
+  GameObject,
+};
 
 pub struct GameState {
   pub camera: camera::Camera,
   pub game_objects: Vec<GameObject>,
+  pub objects_4d: Vec<Object4d>,
   pub collision: Collision,
+  /// clear color, also the sky and fog color while `sky` is `Sky::Color`
   pub background_color: Color,
+  pub sky: Sky,
+  pub lights: Vec<Light>,
+  pub blob_shadow: Option<BlobShadow>,
+  /// colors and width of the outlines around highlighted objects
+  pub outline: OutlineStyle,
+  pub settings: Settings,
+  pub screen_effects: ScreenEffects,
+  /// what the renderer drew in the previous frame, written by the engine after every frame
+  pub frame_stats: FrameStats,
+  /// frame timings, drawn as an overlay while `Profiler::show_overlay` is set
+  pub profiler: Profiler,
 }
 
 impl GameState {
@@ -12,8 +35,17 @@ impl GameState {
     Self {
       camera: camera::Camera::new((-10.0, 5.0, 10.0), cgmath::Deg(90.0), cgmath::Deg(0.0)),
       game_objects: vec![],
+      objects_4d: vec![],
       collision: Collision::new(),
       background_color: Color::from_rgb(1.0, 0.0, 0.0),
+      sky: Sky::default(),
+      lights: vec![],
+      blob_shadow: None,
+      outline: OutlineStyle::default(),
+      settings: Settings::default(),
+      screen_effects: ScreenEffects::default(),
+      frame_stats: FrameStats::default(),
+      profiler: Profiler::new(),
     }
   }
 }
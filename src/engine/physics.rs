@@ -1,7 +1,9 @@
 
 pub mod input;
-pub mod game_object;
+pub mod transform;
 pub mod collision;
+pub mod character;
+pub mod platform;
 
 pub struct State {
   pub input: input::Input,
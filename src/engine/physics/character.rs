@@ -0,0 +1,492 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+use ncollide3d::pipeline::CollisionObjectSlabHandle;
+
+use super::{
+  collision::{Collision, CollisionEvent, QueryFilter, QueryHit, Tag},
+  transform::Transform,
+};
+
+/// Result of a single `CharacterController::move_object` call.
+#[derive(Clone, Copy, Debug)]
+pub struct CharacterMove {
+  pub grounded: bool,
+  pub ground_normal: Vector3<f32>,
+  pub ground_handle: Option<CollisionObjectSlabHandle>,
+  pub hit_wall: bool,
+  pub hit_ceiling: bool,
+  /// displacement inherited from the moving platform the character was standing on
+  pub carried: Vector3<f32>,
+}
+
+/// Kinematic collide-and-slide movement for box shaped characters.
+pub struct CharacterController {
+  /// gap kept between the character and any surface
+  pub skin_width: f32,
+  pub max_step_height: f32,
+  /// steepest walkable slope in degrees
+  pub slope_limit: f32,
+  /// how far down the character is pulled to stay on the ground when walking off a ledge or down a slope
+  pub snap_distance: f32,
+  /// maximum number of slides per move
+  pub max_iterations: u32,
+  grounded: bool,
+  ground_handle: Option<CollisionObjectSlabHandle>,
+}
+
+impl CharacterController {
+  pub fn new() -> Self {
+    Self {
+      skin_width: 0.02,
+      max_step_height: 0.5,
+      slope_limit: 45.0,
+      snap_distance: 0.2,
+      max_iterations: 4,
+      grounded: false,
+      ground_handle: None,
+    }
+  }
+
+  pub fn grounded(&self) -> bool {
+    self.grounded
+  }
+
+  /// Moves `transform` along with the platform it stands on and then by `displacement`, sliding
+  /// along anything in the way, and syncs its collider `handle`, updating `event`.
+  pub fn move_object(
+    &mut self,
+    collision: &mut Collision,
+    handle: CollisionObjectSlabHandle,
+    transform: &mut Transform,
+    event: &mut CollisionEvent,
+    displacement: Vector3<f32>,
+  ) -> CharacterMove {
+    let filter = QueryFilter::tags(&[Tag::Platform]).excluding(handle);
+    let mut result = CharacterMove {
+      grounded: false,
+      ground_normal: Vector3::zero(),
+      ground_handle: None,
+      hit_wall: false,
+      hit_ceiling: false,
+      carried: Vector3::zero(),
+    };
+
+    if let Some(ground) = self.ground_handle {
+      result.carried = collision.point_displacement(ground, transform.position);
+      transform.position += result.carried;
+    }
+
+    self.depenetrate(collision, transform, &filter);
+
+    let horizontal = Vector3::new(displacement.x, 0.0, displacement.z);
+    result.hit_wall = self.slide(collision, transform, horizontal, &filter);
+
+    let vertical = Vector3::new(0.0, displacement.y, 0.0);
+    if !vertical.is_zero() {
+      if let Some(hit) = self.cast(collision, transform, vertical, &filter) {
+        let travel = (hit.distance - self.skin_width).max(0.0);
+        transform.position += vertical.normalize() * travel;
+        if vertical.y < 0.0 {
+          if self.walkable(hit.normal) {
+            result.grounded = true;
+            result.ground_normal = hit.normal;
+            result.ground_handle = Some(hit.handle);
+          } else {
+            let remaining = vertical.magnitude() - travel;
+            self.slide(
+              collision,
+              transform,
+              project_on_plane(vertical.normalize() * remaining, hit.normal),
+              &filter,
+            );
+          }
+        } else {
+          result.hit_ceiling = true;
+        }
+      } else {
+        transform.position += vertical;
+      }
+    }
+
+    if self.grounded && !result.grounded && displacement.y <= 0.0 {
+      let snap = Vector3::new(0.0, -(self.snap_distance + self.skin_width), 0.0);
+      if let Some(hit) = self.cast(collision, transform, snap, &filter) {
+        if self.walkable(hit.normal) {
+          transform.position.y -= (hit.distance - self.skin_width).max(0.0);
+          result.grounded = true;
+          result.ground_normal = hit.normal;
+          result.ground_handle = Some(hit.handle);
+        }
+      }
+    }
+
+    self.grounded = result.grounded;
+    self.ground_handle = result.ground_handle;
+    collision.update_object(handle, transform, event);
+    result
+  }
+
+  fn slide(
+    &self,
+    collision: &mut Collision,
+    transform: &mut Transform,
+    displacement: Vector3<f32>,
+    filter: &QueryFilter,
+  ) -> bool {
+    let mut remaining = displacement;
+    let mut hit_wall = false;
+
+    for _ in 0..self.max_iterations {
+      if remaining.magnitude2() < 0.000001 {
+        break;
+      }
+
+      let Some(hit) = self.cast(collision, transform, remaining, filter) else {
+        transform.position += remaining;
+        break;
+      };
+
+      let direction = remaining.normalize();
+      let travel = (hit.distance - self.skin_width).max(0.0);
+      transform.position += direction * travel;
+      let left = remaining.magnitude() - travel;
+
+      if !self.walkable(hit.normal) {
+        hit_wall = true;
+        if self.grounded && self.step_up(collision, transform, direction * left, filter) {
+          break;
+        }
+      }
+
+      remaining = project_on_plane(direction * left, hit.normal);
+    }
+
+    hit_wall
+  }
+
+  fn step_up(
+    &self,
+    collision: &mut Collision,
+    transform: &mut Transform,
+    displacement: Vector3<f32>,
+    filter: &QueryFilter,
+  ) -> bool {
+    if displacement.is_zero() || self.max_step_height <= 0.0 {
+      return false;
+    }
+
+    let up = Vector3::new(0.0, self.max_step_height, 0.0);
+    let height = match self.cast(collision, transform, up, filter) {
+      Some(hit) => (hit.distance - self.skin_width).max(0.0),
+      None => self.max_step_height,
+    };
+    if height <= 0.0 {
+      return false;
+    }
+
+    let mut raised = *transform;
+    raised.position.y += height;
+    if let Some(hit) = self.cast(collision, &raised, displacement, filter) {
+      if hit.distance <= self.skin_width {
+        return false;
+      }
+      raised.position += displacement.normalize() * (hit.distance - self.skin_width);
+    } else {
+      raised.position += displacement;
+    }
+
+    let down = Vector3::new(0.0, -(height + self.skin_width), 0.0);
+    match self.cast(collision, &raised, down, filter) {
+      Some(hit) if self.walkable(hit.normal) => {
+        raised.position.y -= (hit.distance - self.skin_width).max(0.0);
+        *transform = raised;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  fn depenetrate(
+    &self,
+    collision: &mut Collision,
+    transform: &mut Transform,
+    filter: &QueryFilter,
+  ) {
+    for _ in 0..self.max_iterations {
+      let hits = collision.overlap(transform, filter);
+      let Some(deepest) = hits
+        .iter()
+        .filter(|hit| hit.distance < 0.0)
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+      else {
+        break;
+      };
+      transform.position += deepest.normal * (-deepest.distance + self.skin_width);
+    }
+  }
+
+  fn cast(
+    &self,
+    collision: &mut Collision,
+    transform: &Transform,
+    displacement: Vector3<f32>,
+    filter: &QueryFilter,
+  ) -> Option<QueryHit> {
+    collision.box_cast(
+      transform,
+      displacement,
+      displacement.magnitude() + self.skin_width,
+      filter,
+    )
+  }
+
+  fn walkable(&self, normal: Vector3<f32>) -> bool {
+    normal.y >= self.slope_limit.to_radians().cos()
+  }
+}
+
+impl Default for CharacterController {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn project_on_plane(vector: Vector3<f32>, normal: Vector3<f32>) -> Vector3<f32> {
+  vector - normal * vector.dot(normal)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EPSILON: f32 = 0.01;
+
+  /// A floor with its top at y = 0 and on it, far enough apart not to get in each other's way:
+  /// a wall facing -z, a step the character can climb and one it can't, a 60 degree slope and a
+  /// 30 degree one.
+  struct World {
+    collision: Collision,
+    floor: CollisionObjectSlabHandle,
+    gentle_slope: CollisionObjectSlabHandle,
+  }
+
+  fn world() -> World {
+    let mut collision = Collision::new();
+    let mut add = |position: (f32, f32, f32), rotation: (f32, f32, f32), scale: (f32, f32, f32)| {
+      let transform = Transform::from_components(
+        Some(position.into()),
+        Some(rotation.into()),
+        Some(scale.into()),
+      );
+      collision.add_collider(&transform, &Tag::Platform)
+    };
+
+    let floor = add((0.0, -0.5, 0.0), (0.0, 0.0, 0.0), (50.0, 0.5, 50.0));
+    // wall face at z = 9.5
+    add((0.0, 2.0, 10.0), (0.0, 0.0, 0.0), (5.0, 2.0, 0.5));
+    // 0.4 high, from x = 10 to 14
+    add((12.0, 0.2, 0.0), (0.0, 0.0, 0.0), (2.0, 0.2, 2.0));
+    // 0.8 high, from x = 10 to 14
+    add((12.0, 0.4, -10.0), (0.0, 0.0, 0.0), (2.0, 0.4, 2.0));
+    // both slopes rise towards +x
+    add((-15.0, 0.0, 0.0), (0.0, 0.0, 60.0), (3.0, 0.5, 2.0));
+    let gentle_slope = add((-15.0, 0.0, 10.0), (0.0, 0.0, 30.0), (3.0, 0.5, 2.0));
+
+    World {
+      collision,
+      floor,
+      gentle_slope,
+    }
+  }
+
+  struct Player {
+    handle: CollisionObjectSlabHandle,
+    transform: Transform,
+    event: CollisionEvent,
+  }
+
+  fn player(collision: &mut Collision, x: f32, y: f32, z: f32) -> Player {
+    let transform = Transform::from_components(
+      Some(Vector3::new(x, y, z)),
+      None,
+      Some(Vector3::new(0.5, 0.5, 0.5)),
+    );
+    Player {
+      handle: collision.add_collider(&transform, &Tag::Player),
+      transform,
+      event: CollisionEvent::none(),
+    }
+  }
+
+  fn step(
+    controller: &mut CharacterController,
+    collision: &mut Collision,
+    player: &mut Player,
+    displacement: Vector3<f32>,
+  ) -> CharacterMove {
+    controller.move_object(
+      collision,
+      player.handle,
+      &mut player.transform,
+      &mut player.event,
+      displacement,
+    )
+  }
+
+  /// Lets the character fall onto whatever is below it.
+  fn settle(
+    controller: &mut CharacterController,
+    collision: &mut Collision,
+    player: &mut Player,
+  ) -> CharacterMove {
+    step(controller, collision, player, Vector3::new(0.0, -5.0, 0.0))
+  }
+
+  #[test]
+  fn lands_on_the_floor() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, 0.0, 3.0, 0.0);
+
+    let result = settle(&mut controller, &mut world.collision, &mut object);
+
+    assert!(result.grounded);
+    assert!(controller.grounded());
+    assert_eq!(result.ground_handle, Some(world.floor));
+    assert!((result.ground_normal.y - 1.0).abs() < EPSILON);
+    assert!((object.transform.position.y - (0.5 + controller.skin_width)).abs() < EPSILON);
+  }
+
+  #[test]
+  fn slides_along_walls() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, 0.0, 1.0, 7.0);
+    settle(&mut controller, &mut world.collision, &mut object);
+
+    let result = step(
+      &mut controller,
+      &mut world.collision,
+      &mut object,
+      Vector3::new(1.0, 0.0, 3.0),
+    );
+
+    assert!(result.hit_wall);
+    assert!(result.grounded);
+    let position = object.transform.position;
+    assert!(position.z <= 9.0 && position.z > 9.0 - 2.0 * controller.skin_width);
+    // the part of the move into the wall is turned into movement along it
+    assert!(position.x > 1.0 - EPSILON);
+  }
+
+  #[test]
+  fn climbs_low_steps() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, 8.0, 1.0, 0.0);
+    settle(&mut controller, &mut world.collision, &mut object);
+
+    let result = step(
+      &mut controller,
+      &mut world.collision,
+      &mut object,
+      Vector3::new(3.0, 0.0, 0.0),
+    );
+
+    assert!(result.grounded);
+    let position = object.transform.position;
+    assert!((position.x - 11.0).abs() < EPSILON);
+    assert!((position.y - (0.9 + controller.skin_width)).abs() < EPSILON);
+  }
+
+  #[test]
+  fn stops_at_tall_steps() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, 8.0, 1.0, -10.0);
+    settle(&mut controller, &mut world.collision, &mut object);
+
+    let result = step(
+      &mut controller,
+      &mut world.collision,
+      &mut object,
+      Vector3::new(3.0, 0.0, 0.0),
+    );
+
+    assert!(result.hit_wall);
+    assert!(result.grounded);
+    let position = object.transform.position;
+    assert!(position.x <= 9.5 && position.x > 9.5 - 2.0 * controller.skin_width);
+    assert!((position.y - (0.5 + controller.skin_width)).abs() < EPSILON);
+  }
+
+  #[test]
+  fn slides_off_steep_slopes() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, -15.0, 5.0, 0.0);
+
+    let result = settle(&mut controller, &mut world.collision, &mut object);
+
+    assert!(!result.grounded);
+    assert_eq!(result.ground_handle, None);
+    // pushed downhill instead of resting where it landed
+    assert!(object.transform.position.x < -15.0);
+  }
+
+  #[test]
+  fn stands_on_walkable_slopes() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, -15.0, 5.0, 10.0);
+
+    let result = settle(&mut controller, &mut world.collision, &mut object);
+
+    assert!(result.grounded);
+    assert_eq!(result.ground_handle, Some(world.gentle_slope));
+    assert!((result.ground_normal.y - 30f32.to_radians().cos()).abs() < EPSILON);
+    assert!((object.transform.position.x + 15.0).abs() < EPSILON);
+  }
+
+  #[test]
+  fn snaps_down_slopes() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, -15.0, 5.0, 10.0);
+    settle(&mut controller, &mut world.collision, &mut object);
+
+    // every move leaves the slope by 0.1 * tan(30) ~ 0.06, which is within the snap distance
+    for _ in 0..10 {
+      let y = object.transform.position.y;
+      let result = step(
+        &mut controller,
+        &mut world.collision,
+        &mut object,
+        Vector3::new(-0.1, 0.0, 0.0),
+      );
+      assert!(result.grounded);
+      assert_eq!(result.ground_handle, Some(world.gentle_slope));
+      assert!(object.transform.position.y < y);
+    }
+  }
+
+  #[test]
+  fn does_not_snap_down_ledges() {
+    let mut world = world();
+    let mut controller = CharacterController::new();
+    let mut object = player(&mut world.collision, 13.0, 2.0, -10.0);
+    settle(&mut controller, &mut world.collision, &mut object);
+    let y = object.transform.position.y;
+
+    // the floor is 0.8 below the top of the tall step, out of snap distance
+    let result = step(
+      &mut controller,
+      &mut world.collision,
+      &mut object,
+      Vector3::new(2.0, 0.0, 0.0),
+    );
+
+    assert!(!result.grounded);
+    assert!(!controller.grounded());
+    assert_eq!(object.transform.position.y, y);
+  }
+}
@@ -1,14 +1,19 @@
 use cgmath::Zero;
 use ncollide3d::{
+  bounding_volume::BoundingVolume,
   na::{self, Isometry3, Translation3, UnitQuaternion},
   pipeline::{CollisionGroups, CollisionObjectSlabHandle, GeometricQueryType},
-  query::{self, Contact, DefaultTOIDispatcher},
-  shape::{Cuboid, ShapeHandle},
+  query::{self, Contact, DefaultTOIDispatcher, Ray},
+  shape::{Ball, Cuboid, Shape, ShapeHandle},
   world::CollisionWorld,
 };
-use std::{collections::HashMap, f32::consts::PI};
+use std::{
+  collections::{HashMap, HashSet},
+  f32::consts::PI,
+  time::Duration,
+};
 
-use crate::engine::{GameObject, Transform};
+use super::transform::Transform;
 
 #[derive(Clone, Copy, Debug)]
 pub enum EventStatus {
@@ -27,47 +32,150 @@ pub struct CollisionEvent {
   pub other_tag: Tag,
 }
 
-#[derive(Copy, Clone)]
+impl CollisionEvent {
+  pub fn none() -> Self {
+    Self {
+      status: EventStatus::None,
+      depth: 0.0,
+      normal: cgmath::Vector3::zero(),
+      other_handle: CollisionObjectSlabHandle(0),
+      other_tag: Tag::None,
+    }
+  }
+}
+
+/// Emitted by `Collision::update_triggers` for every object overlapping a trigger volume.
+#[derive(Clone, Copy, Debug)]
+pub struct TriggerEvent {
+  pub status: EventStatus,
+  pub trigger: CollisionObjectSlabHandle,
+  pub other: CollisionObjectSlabHandle,
+  pub other_tag: Tag,
+}
+
+#[derive(Copy, Clone, Debug)]
 pub enum Tag {
   Player,
   Platform,
+  /// non-solid volume that reports overlaps through `TriggerEvent`s
+  Trigger,
   None,
 }
 
+/// Result of a raycast, shape cast or overlap query.
+#[derive(Clone, Copy, Debug)]
+pub struct QueryHit {
+  pub handle: CollisionObjectSlabHandle,
+  pub tag: Tag,
+  pub point: cgmath::Vector3<f32>,
+  pub normal: cgmath::Vector3<f32>,
+  /// distance travelled along the cast direction, or penetration depth (negative) for overlaps
+  pub distance: f32,
+}
+
+/// Restricts which colliders a query can hit.
+#[derive(Clone, Copy)]
+pub struct QueryFilter {
+  groups: CollisionGroups,
+  exclude: Option<CollisionObjectSlabHandle>,
+}
+
+impl QueryFilter {
+  /// Every solid collider, trigger volumes are only hit when asked for with `tags`.
+  pub fn all() -> Self {
+    let mut groups = CollisionGroups::new();
+    groups.set_blacklist(&[group_id(&Tag::Trigger)]);
+    Self {
+      groups,
+      exclude: None,
+    }
+  }
+
+  pub fn tags(tags: &[Tag]) -> Self {
+    let ids = tags.iter().map(group_id).collect::<Vec<_>>();
+    let mut groups = CollisionGroups::new();
+    groups.set_whitelist(&ids);
+    Self {
+      groups,
+      exclude: None,
+    }
+  }
+
+  pub fn excluding(self, handle: CollisionObjectSlabHandle) -> Self {
+    Self {
+      exclude: Some(handle),
+      ..self
+    }
+  }
+
+  fn accepts(&self, handle: CollisionObjectSlabHandle) -> bool {
+    self.exclude != Some(handle)
+  }
+}
+
 pub struct Collision {
   world: CollisionWorld<f32, Tag>,
   player_group: CollisionGroups,
   platform_group: CollisionGroups,
+  none_group: CollisionGroups,
+  trigger_group: CollisionGroups,
   contacts_query: GeometricQueryType<f32>,
+  proximity_query: GeometricQueryType<f32>,
+  trigger_overlaps: HashSet<(CollisionObjectSlabHandle, CollisionObjectSlabHandle)>,
+  trigger_events: Vec<TriggerEvent>,
+  motions: HashMap<CollisionObjectSlabHandle, Isometry3<f32>>,
+  /// time spent in queries and collider updates since the last `take_elapsed`
+  elapsed: Duration,
 }
 
 impl Collision {
   pub fn new() -> Self {
     let mut player_group = CollisionGroups::new();
-    player_group.set_membership(&[1]);
+    player_group.set_membership(&[group_id(&Tag::Player)]);
     let mut platform_group = CollisionGroups::new();
-    platform_group.set_membership(&[2]);
-    platform_group.set_whitelist(&[1]);
+    platform_group.set_membership(&[group_id(&Tag::Platform)]);
+    platform_group.set_whitelist(&[group_id(&Tag::Player)]);
+    let mut none_group = CollisionGroups::new();
+    none_group.set_membership(&[group_id(&Tag::None)]);
+    none_group.set_whitelist(&[group_id(&Tag::Player)]);
+    let mut trigger_group = CollisionGroups::new();
+    trigger_group.set_membership(&[group_id(&Tag::Trigger)]);
+    trigger_group.set_whitelist(&[group_id(&Tag::Player)]);
 
     let world = CollisionWorld::new(0.02);
 
     let contacts_query = GeometricQueryType::Contacts(0.0, 0.0);
+    let proximity_query = GeometricQueryType::Proximity(0.0);
 
     Self {
       world,
       player_group,
       platform_group,
+      none_group,
+      trigger_group,
       contacts_query,
+      proximity_query,
+      trigger_overlaps: HashSet::new(),
+      trigger_events: Vec::new(),
+      motions: HashMap::new(),
+      elapsed: Duration::ZERO,
     }
   }
 
-  pub fn update_object(&mut self, object: &mut GameObject) {
+  /// Syncs the collider behind `handle` with `transform` and updates `event` with what it touches.
+  pub fn update_object(
+    &mut self,
+    handle: CollisionObjectSlabHandle,
+    transform: &Transform,
+    event: &mut CollisionEvent,
+  ) {
+    let started = instant::Instant::now();
     let mut events =
       HashMap::<CollisionObjectSlabHandle, (CollisionObjectSlabHandle, Contact<f32>)>::new();
 
-    let collision_object = self.world.get_mut(object.collision_handle).unwrap();
-    collision_object.set_position(get_isometry(&object.transform));
-    let cgmath::Vector3 { x, y, z } = object.transform.scale;
+    let collision_object = self.world.get_mut(handle).unwrap();
+    collision_object.set_position(get_isometry(transform));
+    let cgmath::Vector3 { x, y, z } = transform.scale;
     collision_object.set_shape(ShapeHandle::new(Cuboid::new(na::Vector3::<f32>::new(
       x, y, z,
     ))));
@@ -79,14 +187,14 @@ impl Collision {
       events.insert(pair.1, (pair.0, pair.3.deepest_contact().unwrap().contact));
     }
 
-    if let Some((other_handle, contact)) = events.get(&object.collision_handle) {
-      let status = match object.collision.status {
+    if let Some((other_handle, contact)) = events.get(&handle) {
+      let status = match event.status {
         EventStatus::None => EventStatus::Enter,
         EventStatus::Enter => EventStatus::Stay,
         EventStatus::Stay => EventStatus::Stay,
         EventStatus::Leave => EventStatus::Enter,
       };
-      object.collision = CollisionEvent {
+      *event = CollisionEvent {
         status,
         depth: contact.depth,
         normal: cgmath::Vector3 {
@@ -98,21 +206,13 @@ impl Collision {
         other_tag: Tag::Platform,
       };
     } else {
-      if let EventStatus::Enter | EventStatus::Stay = object.collision.status {
-        object.collision = CollisionEvent {
-          status: EventStatus::Leave,
-          ..object.collision
-        };
+      if let EventStatus::Enter | EventStatus::Stay = event.status {
+        event.status = EventStatus::Leave;
       } else {
-        object.collision = CollisionEvent {
-          status: EventStatus::None,
-          depth: 0.0,
-          normal: cgmath::Vector3::zero(),
-          other_handle: CollisionObjectSlabHandle(0),
-          other_tag: Tag::None,
-        };
+        *event = CollisionEvent::none();
       }
     }
+    self.elapsed += started.elapsed();
   }
 
   pub fn get_toi(
@@ -145,6 +245,254 @@ impl Collision {
     1.0
   }
 
+  /// Compares trigger overlaps against the previous call and queues enter/stay/leave events.
+  pub fn update_triggers(&mut self) {
+    let started = instant::Instant::now();
+    self.world.update();
+
+    let mut overlaps = HashSet::new();
+    for (a, b, _, _) in self.world.proximity_pairs(true) {
+      let a_is_trigger = matches!(self.world.collision_object(a).unwrap().data(), Tag::Trigger);
+      overlaps.insert(if a_is_trigger { (a, b) } else { (b, a) });
+    }
+
+    let mut changes = overlaps
+      .iter()
+      .map(|&(trigger, other)| {
+        let status = if self.trigger_overlaps.contains(&(trigger, other)) {
+          EventStatus::Stay
+        } else {
+          EventStatus::Enter
+        };
+        (status, trigger, other)
+      })
+      .collect::<Vec<_>>();
+    changes.extend(
+      self
+        .trigger_overlaps
+        .difference(&overlaps)
+        .map(|&(trigger, other)| (EventStatus::Leave, trigger, other)),
+    );
+
+    for (status, trigger, other) in changes {
+      let other_tag = self.tag_of(other);
+      self.trigger_events.push(TriggerEvent {
+        status,
+        trigger,
+        other,
+        other_tag,
+      });
+    }
+
+    self.trigger_overlaps = overlaps;
+    self.elapsed += started.elapsed();
+  }
+
+  /// Takes the time spent in queries and collider updates since the last call, the engine
+  /// reports it as the physics part of the frame.
+  pub fn take_elapsed(&mut self) -> Duration {
+    std::mem::take(&mut self.elapsed)
+  }
+
+  /// Takes every trigger event queued since the last call.
+  pub fn drain_trigger_events(&mut self) -> Vec<TriggerEvent> {
+    std::mem::take(&mut self.trigger_events)
+  }
+
+  fn tag_of(&self, handle: CollisionObjectSlabHandle) -> Tag {
+    match self.world.collision_object(handle) {
+      Some(object) => *object.data(),
+      None => Tag::None,
+    }
+  }
+
+  /// Moves a collider without checking it for contacts, remembering how far it moved since the last call.
+  pub fn move_collider(&mut self, handle: CollisionObjectSlabHandle, object: &Transform) {
+    let started = instant::Instant::now();
+    let isometry = get_isometry(object);
+    let collision_object = self.world.get_mut(handle).unwrap();
+    let motion = isometry * collision_object.position().inverse();
+    collision_object.set_position(isometry);
+    self.motions.insert(handle, motion);
+    self.elapsed += started.elapsed();
+  }
+
+  /// How far a point attached to the collider moved during its last `move_collider` call.
+  pub fn point_displacement(
+    &self,
+    handle: CollisionObjectSlabHandle,
+    point: cgmath::Vector3<f32>,
+  ) -> cgmath::Vector3<f32> {
+    match self.motions.get(&handle) {
+      Some(motion) => {
+        let point = na::Point3::from(to_na(point));
+        to_cgmath(&(motion.transform_point(&point) - point))
+      }
+      None => cgmath::Vector3::zero(),
+    }
+  }
+
+  /// Casts a ray and returns the closest hit within `max_distance`.
+  pub fn raycast(
+    &mut self,
+    origin: cgmath::Vector3<f32>,
+    direction: cgmath::Vector3<f32>,
+    max_distance: f32,
+    filter: &QueryFilter,
+  ) -> Option<QueryHit> {
+    if direction.is_zero() {
+      return None;
+    }
+    let started = instant::Instant::now();
+    self.world.perform_broad_phase();
+
+    let direction = cgmath::InnerSpace::normalize(direction);
+    let ray = Ray::new(na::Point3::from(to_na(origin)), to_na(direction));
+
+    let hit = self
+      .world
+      .interferences_with_ray(&ray, max_distance, &filter.groups)
+      .filter(|(handle, _, _)| filter.accepts(*handle))
+      .min_by(|(_, _, a), (_, _, b)| a.toi.total_cmp(&b.toi))
+      .map(|(handle, object, intersection)| QueryHit {
+        handle,
+        tag: *object.data(),
+        point: origin + direction * intersection.toi,
+        normal: to_cgmath(&intersection.normal),
+        distance: intersection.toi,
+      });
+    self.elapsed += started.elapsed();
+    hit
+  }
+
+  /// Sweeps a sphere from `origin` along `direction` and returns the first hit.
+  pub fn sphere_cast(
+    &mut self,
+    origin: cgmath::Vector3<f32>,
+    radius: f32,
+    direction: cgmath::Vector3<f32>,
+    max_distance: f32,
+    filter: &QueryFilter,
+  ) -> Option<QueryHit> {
+    let isometry = Isometry3::from_parts(
+      Translation3::from(to_na(origin)),
+      UnitQuaternion::identity(),
+    );
+    self.shape_cast(
+      &Ball::new(radius),
+      &isometry,
+      direction,
+      max_distance,
+      filter,
+    )
+  }
+
+  /// Sweeps a box with the size and orientation of `object` along `direction` and returns the first hit.
+  pub fn box_cast(
+    &mut self,
+    object: &Transform,
+    direction: cgmath::Vector3<f32>,
+    max_distance: f32,
+    filter: &QueryFilter,
+  ) -> Option<QueryHit> {
+    let shape = Cuboid::new(to_na(object.scale));
+    self.shape_cast(
+      &shape,
+      &get_isometry(object),
+      direction,
+      max_distance,
+      filter,
+    )
+  }
+
+  /// Returns every collider intersecting a box with the size and orientation of `object`.
+  pub fn overlap(&mut self, object: &Transform, filter: &QueryFilter) -> Vec<QueryHit> {
+    let started = instant::Instant::now();
+    self.world.perform_broad_phase();
+
+    let shape = Cuboid::new(to_na(object.scale));
+    let isometry = get_isometry(object);
+    let aabb = shape.aabb(&isometry);
+
+    let hits = self
+      .world
+      .interferences_with_aabb(&aabb, &filter.groups)
+      .filter(|(handle, _)| filter.accepts(*handle))
+      .filter_map(|(handle, other)| {
+        query::contact(
+          &isometry,
+          &shape,
+          other.position(),
+          other.shape().as_ref(),
+          0.0,
+        )
+        .map(|contact| QueryHit {
+          handle,
+          tag: *other.data(),
+          point: to_cgmath(&contact.world2.coords),
+          normal: -to_cgmath(&contact.normal),
+          distance: -contact.depth,
+        })
+      })
+      .collect();
+    self.elapsed += started.elapsed();
+    hits
+  }
+
+  fn shape_cast(
+    &mut self,
+    shape: &dyn Shape<f32>,
+    isometry: &Isometry3<f32>,
+    direction: cgmath::Vector3<f32>,
+    max_distance: f32,
+    filter: &QueryFilter,
+  ) -> Option<QueryHit> {
+    if direction.is_zero() {
+      return None;
+    }
+    let started = instant::Instant::now();
+    self.world.perform_broad_phase();
+
+    let direction = to_na(cgmath::InnerSpace::normalize(direction));
+    let start = shape.aabb(isometry);
+    let end = shape.aabb(&Isometry3::from_parts(
+      Translation3::from(isometry.translation.vector + direction * max_distance),
+      isometry.rotation,
+    ));
+    let swept = start.merged(&end);
+
+    let hit = self
+      .world
+      .interferences_with_aabb(&swept, &filter.groups)
+      .filter(|(handle, _)| filter.accepts(*handle))
+      .filter_map(|(handle, other)| {
+        query::time_of_impact::<f32>(
+          &DefaultTOIDispatcher,
+          isometry,
+          &direction,
+          shape,
+          other.position(),
+          &na::Vector3::<f32>::zeros(),
+          other.shape().as_ref(),
+          max_distance,
+          0.0,
+        )
+        .ok()
+        .flatten()
+        .map(|toi| (handle, other, toi))
+      })
+      .min_by(|(_, _, a), (_, _, b)| a.toi.total_cmp(&b.toi))
+      .map(|(handle, other, toi)| QueryHit {
+        handle,
+        tag: *other.data(),
+        point: to_cgmath(&other.position().transform_point(&toi.witness2).coords),
+        normal: to_cgmath(&other.position().transform_vector(&toi.normal2)),
+        distance: toi.toi,
+      });
+    self.elapsed += started.elapsed();
+    hit
+  }
+
   pub fn add_collider(&mut self, object: &Transform, tag: &Tag) -> CollisionObjectSlabHandle {
     let position = get_isometry(object);
 
@@ -154,7 +502,12 @@ impl Collision {
     let collision_group = match tag {
       Tag::Player => self.player_group,
       Tag::Platform => self.platform_group,
-      _ => self.platform_group,
+      Tag::None => self.none_group,
+      Tag::Trigger => self.trigger_group,
+    };
+    let query_type = match tag {
+      Tag::Trigger => self.proximity_query,
+      _ => self.contacts_query,
     };
 
     let collision_data = *tag;
@@ -163,7 +516,7 @@ impl Collision {
       position,
       collider,
       collision_group,
-      self.contacts_query,
+      query_type,
       collision_data,
     );
 
@@ -171,6 +524,23 @@ impl Collision {
   }
 }
 
+fn group_id(tag: &Tag) -> usize {
+  match tag {
+    Tag::Player => 1,
+    Tag::Platform => 2,
+    Tag::None => 3,
+    Tag::Trigger => 4,
+  }
+}
+
+fn to_na(v: cgmath::Vector3<f32>) -> na::Vector3<f32> {
+  na::Vector3::<f32>::new(v.x, v.y, v.z)
+}
+
+fn to_cgmath(v: &na::Vector3<f32>) -> cgmath::Vector3<f32> {
+  cgmath::Vector3::new(v.x, v.y, v.z)
+}
+
 fn get_isometry(obj: &Transform) -> Isometry3<f32> {
   let cgmath::Vector3 { x, y, z } = obj.position;
   let position = Translation3::from(na::Vector3::<f32>::new(x, y, z));
@@ -179,3 +549,281 @@ fn get_isometry(obj: &Transform) -> Isometry3<f32> {
 
   Isometry3::from_parts(position, rotation)
 }
+
+#[cfg(test)]
+mod tests {
+  use cgmath::{InnerSpace, Vector3};
+
+  use super::*;
+
+  const EPSILON: f32 = 0.001;
+
+  fn add(
+    collision: &mut Collision,
+    position: (f32, f32, f32),
+    rotation: (f32, f32, f32),
+    scale: (f32, f32, f32),
+    tag: Tag,
+  ) -> CollisionObjectSlabHandle {
+    let transform = Transform::from_components(
+      Some(position.into()),
+      Some(rotation.into()),
+      Some(scale.into()),
+    );
+    collision.add_collider(&transform, &tag)
+  }
+
+  fn assert_near(a: Vector3<f32>, b: Vector3<f32>) {
+    assert!((a - b).magnitude() < EPSILON, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn raycast_hits_box() {
+    let mut collision = Collision::new();
+    let handle = add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 1.0, 1.0),
+      Tag::Platform,
+    );
+
+    let hit = collision
+      .raycast(
+        Vector3::new(0.0, 5.0, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        10.0,
+        &QueryFilter::all(),
+      )
+      .unwrap();
+
+    assert_eq!(hit.handle, handle);
+    assert!(matches!(hit.tag, Tag::Platform));
+    assert!((hit.distance - 4.0).abs() < EPSILON);
+    assert_near(hit.point, Vector3::new(0.0, 1.0, 0.0));
+    assert_near(hit.normal, Vector3::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn raycast_misses() {
+    let mut collision = Collision::new();
+    add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 1.0, 1.0),
+      Tag::Platform,
+    );
+    let origin = Vector3::new(0.0, 5.0, 0.0);
+    let filter = QueryFilter::all();
+
+    assert!(collision
+      .raycast(origin, Vector3::new(0.0, 1.0, 0.0), 10.0, &filter)
+      .is_none());
+    // out of reach
+    assert!(collision
+      .raycast(origin, Vector3::new(0.0, -1.0, 0.0), 3.0, &filter)
+      .is_none());
+    assert!(collision
+      .raycast(origin, Vector3::new(0.0, 0.0, 0.0), 10.0, &filter)
+      .is_none());
+  }
+
+  #[test]
+  fn filters_by_tag() {
+    let mut collision = Collision::new();
+    // stacked under the ray origin, closest first
+    add(
+      &mut collision,
+      (0.0, 4.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Player,
+    );
+    add(
+      &mut collision,
+      (0.0, 2.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::None,
+    );
+    let platform = add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Platform,
+    );
+    let origin = Vector3::new(0.0, 10.0, 0.0);
+    let down = Vector3::new(0.0, -1.0, 0.0);
+
+    let hit = collision
+      .raycast(origin, down, 20.0, &QueryFilter::all())
+      .unwrap();
+    assert!(matches!(hit.tag, Tag::Player));
+
+    let hit = collision
+      .raycast(origin, down, 20.0, &QueryFilter::tags(&[Tag::Platform]))
+      .unwrap();
+    assert_eq!(hit.handle, platform);
+
+    let hit = collision
+      .raycast(origin, down, 20.0, &QueryFilter::tags(&[Tag::None]))
+      .unwrap();
+    assert!(matches!(hit.tag, Tag::None));
+  }
+
+  #[test]
+  fn skips_triggers_unless_asked() {
+    let mut collision = Collision::new();
+    add(
+      &mut collision,
+      (0.0, 2.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Trigger,
+    );
+    let platform = add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Platform,
+    );
+    let origin = Vector3::new(0.0, 10.0, 0.0);
+    let down = Vector3::new(0.0, -1.0, 0.0);
+
+    let hit = collision
+      .raycast(origin, down, 20.0, &QueryFilter::all())
+      .unwrap();
+    assert_eq!(hit.handle, platform);
+    let hit = collision
+      .raycast(origin, down, 20.0, &QueryFilter::tags(&[Tag::Trigger]))
+      .unwrap();
+    assert!(matches!(hit.tag, Tag::Trigger));
+  }
+
+  #[test]
+  fn excludes_handle() {
+    let mut collision = Collision::new();
+    let near = add(
+      &mut collision,
+      (0.0, 2.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Platform,
+    );
+    let far = add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 0.5, 1.0),
+      Tag::Platform,
+    );
+    let origin = Vector3::new(0.0, 10.0, 0.0);
+    let down = Vector3::new(0.0, -1.0, 0.0);
+
+    let filter = QueryFilter::all();
+    assert_eq!(
+      collision
+        .raycast(origin, down, 20.0, &filter)
+        .unwrap()
+        .handle,
+      near
+    );
+    let hit = collision
+      .sphere_cast(origin, 0.5, down, 20.0, &filter.excluding(near))
+      .unwrap();
+    assert_eq!(hit.handle, far);
+    assert!(collision
+      .overlap(
+        &Transform::from_position(Vector3::new(0.0, 2.0, 0.0)),
+        &filter.excluding(near)
+      )
+      .is_empty());
+  }
+
+  #[test]
+  fn shape_cast_hit_is_in_world_space() {
+    let mut collision = Collision::new();
+    // turned a quarter around y, the local z half extent of 2 ends up along x
+    add(
+      &mut collision,
+      (5.0, 0.0, 0.0),
+      (0.0, 90.0, 0.0),
+      (0.5, 1.0, 2.0),
+      Tag::Platform,
+    );
+    let filter = QueryFilter::all();
+
+    let hit = collision
+      .sphere_cast(
+        Vector3::new(0.0, 0.0, 0.0),
+        0.5,
+        Vector3::new(1.0, 0.0, 0.0),
+        10.0,
+        &filter,
+      )
+      .unwrap();
+    assert!((hit.distance - 2.5).abs() < EPSILON);
+    assert_near(hit.point, Vector3::new(3.0, 0.0, 0.0));
+    assert_near(hit.normal, Vector3::new(-1.0, 0.0, 0.0));
+
+    let hit = collision
+      .box_cast(
+        &Transform::from_position(Vector3::new(5.0, 5.0, 0.0)),
+        Vector3::new(0.0, -1.0, 0.0),
+        10.0,
+        &filter,
+      )
+      .unwrap();
+    assert!((hit.distance - 3.0).abs() < EPSILON);
+    assert!((hit.point.y - 1.0).abs() < EPSILON);
+    assert_near(hit.normal, Vector3::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn overlap_reports_penetration() {
+    let mut collision = Collision::new();
+    let handle = add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 1.0, 1.0),
+      Tag::Platform,
+    );
+
+    let hits = collision.overlap(
+      &Transform::from_position(Vector3::new(0.0, 1.75, 0.0)),
+      &QueryFilter::all(),
+    );
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].handle, handle);
+    assert!((hits[0].distance + 0.25).abs() < EPSILON);
+    // pointing out of the box that was hit, the way out for the overlapping one
+    assert_near(hits[0].normal, Vector3::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn accumulates_query_time_until_taken() {
+    let mut collision = Collision::new();
+    add(
+      &mut collision,
+      (0.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 1.0, 1.0),
+      Tag::Platform,
+    );
+
+    collision.raycast(
+      Vector3::new(0.0, 5.0, 0.0),
+      Vector3::new(0.0, -1.0, 0.0),
+      10.0,
+      &QueryFilter::all(),
+    );
+
+    assert!(collision.take_elapsed() > Duration::ZERO);
+    assert_eq!(collision.take_elapsed(), Duration::ZERO);
+  }
+}
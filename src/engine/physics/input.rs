@@ -21,9 +21,11 @@ pub struct Input {
   internal_key_states: HashMap<VirtualKeyCode, bool>,
   key_states: HashMap<VirtualKeyCode, KeyState>,
   mouse_states: HashMap<MouseButton, bool>,
+  mouse_button_states: HashMap<MouseButton, KeyState>,
   mouse_position: Vector2<f32>,
   prev_mouse_position: Vector2<f32>,
   mouse_speed: Vector2<f32>,
+  scroll_delta: f32,
   scroll_speed: f32,
 }
 
@@ -33,9 +35,11 @@ impl Input {
       internal_key_states: HashMap::new(),
       key_states: HashMap::new(),
       mouse_states: HashMap::new(),
+      mouse_button_states: HashMap::new(),
       mouse_position: Vector2 { x: 0.0, y: 0.0 },
       prev_mouse_position: Vector2 { x: 0.0, y: 0.0 },
       mouse_speed: Vector2 { x: 0.0, y: 0.0 },
+      scroll_delta: 0.0,
       scroll_speed: 0.0,
     }
   }
@@ -57,7 +61,7 @@ impl Input {
     self.mouse_position.y += mouse_dy as f32;
   }
   fn process_scroll(&mut self, delta: f32) {
-    self.scroll_speed = delta;
+    self.scroll_delta += delta;
   }
 
   pub fn key_pressed(&self, key: VirtualKeyCode) -> bool {
@@ -74,6 +78,14 @@ impl Input {
     }
   }
 
+  /// True on the frame the key is pressed and every frame it stays held.
+  pub fn key_down(&self, key: VirtualKeyCode) -> bool {
+    matches!(
+      self.key_states.get(&key),
+      Some(KeyState::Pressed | KeyState::Held)
+    )
+  }
+
   pub fn key_released(&self, key: VirtualKeyCode) -> bool {
     match self.key_states.get(&key) {
       Some(KeyState::Released) => true,
@@ -81,6 +93,7 @@ impl Input {
     }
   }
 
+  /// True every frame the button is held down.
   pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
     match self.mouse_states.get(&button) {
       Some(state) => *state,
@@ -88,6 +101,14 @@ impl Input {
     }
   }
 
+  /// True only on the frame the button goes down, unlike `mouse_button_pressed`.
+  pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+    matches!(
+      self.mouse_button_states.get(&button),
+      Some(KeyState::Pressed)
+    )
+  }
+
   pub fn get_mouse_position(&self) -> Vector2<f32> {
     Vector2 {
       x: self.mouse_position.x,
@@ -101,6 +122,11 @@ impl Input {
     }
   }
 
+  /// Scroll wheel movement since the last frame, positive when scrolling down.
+  pub fn get_scroll_speed(&self) -> f32 {
+    self.scroll_speed
+  }
+
   pub fn handle_event(&mut self, event: &Event<'_, ()>) {
     match event {
       Event::DeviceEvent {
@@ -149,28 +175,17 @@ impl Input {
   pub fn update(&mut self) {
     self.mouse_speed = (self.mouse_position - self.prev_mouse_position) * 0.1;
     self.prev_mouse_position = self.mouse_position;
+    self.scroll_speed = self.scroll_delta;
+    self.scroll_delta = 0.0;
 
     for (code, pressed) in &self.internal_key_states {
-      let mut new_state = KeyState::Pressed;
-      if let Some(state) = self.key_states.get(&code) {
-        if *pressed {
-          new_state = match state {
-            KeyState::None => KeyState::Pressed,
-            KeyState::Pressed => KeyState::Held,
-            KeyState::Held => KeyState::Held,
-            KeyState::Released => KeyState::Released,
-          }
-        } else {
-          new_state = match state {
-            KeyState::None => KeyState::None,
-            KeyState::Pressed => KeyState::Released,
-            KeyState::Held => KeyState::Released,
-            KeyState::Released => KeyState::None,
-          }
-        }
-      }
+      let new_state = next_state(self.key_states.get(code), *pressed);
       self.key_states.insert(*code, new_state);
     }
+    for (button, pressed) in &self.mouse_states {
+      let new_state = next_state(self.mouse_button_states.get(button), *pressed);
+      self.mouse_button_states.insert(*button, new_state);
+    }
   }
 
   pub fn updated_window_size(&mut self, window: &Window) {
@@ -183,3 +198,23 @@ impl Input {
     }
   }
 }
+
+/// Advances a key or mouse button by a frame, given whether it is currently down.
+fn next_state(state: Option<&KeyState>, pressed: bool) -> KeyState {
+  let state = state.unwrap_or(&KeyState::None);
+  if pressed {
+    match state {
+      KeyState::None => KeyState::Pressed,
+      KeyState::Pressed => KeyState::Held,
+      KeyState::Held => KeyState::Held,
+      KeyState::Released => KeyState::Released,
+    }
+  } else {
+    match state {
+      KeyState::None => KeyState::None,
+      KeyState::Pressed => KeyState::Released,
+      KeyState::Held => KeyState::Released,
+      KeyState::Released => KeyState::None,
+    }
+  }
+}
@@ -0,0 +1,151 @@
+use cgmath::{Vector3, VectorSpace, Zero};
+
+use ncollide3d::pipeline::CollisionObjectSlabHandle;
+
+use super::{collision::Collision, transform::Transform};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Easing {
+  Linear,
+  EaseIn,
+  EaseOut,
+  EaseInOut,
+}
+
+impl Easing {
+  pub fn apply(&self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      Easing::Linear => t,
+      Easing::EaseIn => t * t,
+      Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+      Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LoopMode {
+  /// travel the path once and stop at the last waypoint
+  Once,
+  /// return from the last waypoint to the first and repeat
+  Loop,
+  /// travel the path forwards then backwards
+  PingPong,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Waypoint {
+  pub position: Vector3<f32>,
+  /// seconds it takes to travel from this waypoint to the next one
+  pub duration: f32,
+  pub easing: Easing,
+}
+
+impl Waypoint {
+  pub fn new(position: (f32, f32, f32), duration: f32, easing: Easing) -> Self {
+    Self {
+      position: position.into(),
+      duration,
+      easing,
+    }
+  }
+}
+
+/// Moves a platform along a path of waypoints and/or spins it, moving its collider every tick.
+pub struct AnimatedPlatform {
+  pub waypoints: Vec<Waypoint>,
+  pub loop_mode: LoopMode,
+  /// rotation speed around each axis in degrees per second
+  pub spin: Vector3<f32>,
+  time: f32,
+}
+
+impl AnimatedPlatform {
+  pub fn new(waypoints: Vec<Waypoint>, loop_mode: LoopMode) -> Self {
+    Self {
+      waypoints,
+      loop_mode,
+      spin: Vector3::zero(),
+      time: 0.0,
+    }
+  }
+
+  /// Moves `transform` to the first waypoint, if there is one.
+  pub fn place(&self, transform: &mut Transform) {
+    if let Some(position) = self.position_at(0.0) {
+      transform.position = position;
+    }
+  }
+
+  pub fn update(
+    &mut self,
+    collision: &mut Collision,
+    handle: CollisionObjectSlabHandle,
+    transform: &mut Transform,
+    dt: f32,
+  ) {
+    self.time += dt;
+
+    if let Some(position) = self.position_at(self.time) {
+      transform.position = position;
+    }
+
+    transform.rotation += self.spin * dt;
+    transform.rotation.x %= 360.0;
+    transform.rotation.y %= 360.0;
+    transform.rotation.z %= 360.0;
+
+    collision.move_collider(handle, transform);
+  }
+
+  fn position_at(&self, time: f32) -> Option<Vector3<f32>> {
+    let count = self.waypoints.len();
+    if count == 0 {
+      return None;
+    }
+    if count == 1 {
+      return Some(self.waypoints[0].position);
+    }
+
+    let path_length = |segments: usize| -> f32 {
+      self.waypoints[..segments]
+        .iter()
+        .map(|waypoint| waypoint.duration)
+        .sum()
+    };
+
+    let (mut time, segments) = match self.loop_mode {
+      LoopMode::Once => (time.min(path_length(count - 1)), count - 1),
+      LoopMode::Loop => (time % path_length(count).max(f32::EPSILON), count),
+      LoopMode::PingPong => {
+        let length = path_length(count - 1).max(f32::EPSILON);
+        let time = time % (length * 2.0);
+        (
+          if time > length {
+            length * 2.0 - time
+          } else {
+            time
+          },
+          count - 1,
+        )
+      }
+    };
+
+    for i in 0..segments {
+      let waypoint = &self.waypoints[i];
+      let next = &self.waypoints[(i + 1) % count];
+      if time <= waypoint.duration || i == segments - 1 {
+        let t = if waypoint.duration > 0.0 {
+          waypoint.easing.apply(time / waypoint.duration)
+        } else {
+          1.0
+        };
+        return Some(waypoint.position.lerp(next.position, t));
+      }
+      time -= waypoint.duration;
+    }
+
+    None
+  }
+}
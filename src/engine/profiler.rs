@@ -0,0 +1,164 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// frames kept for the frame time graph
+pub const HISTORY_FRAMES: usize = 120;
+/// how much of every new sample goes into the smoothed values, lower is steadier
+const SMOOTHING: f32 = 0.05;
+
+/// The parts of a frame the CPU time is split into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuSection {
+  /// polling the keyboard and mouse state
+  Input,
+  /// `Scene::update` and the trigger callbacks, without the collision work they do
+  Update,
+  /// collision queries, collider moves and trigger overlaps, timed by `Collision` itself
+  Physics,
+  /// building the render queue and encoding and submitting the frame
+  Render,
+  /// waiting for the next surface texture, which is where vsync blocks
+  SurfaceWait,
+}
+
+impl CpuSection {
+  pub const ALL: [CpuSection; 5] = [
+    CpuSection::Input,
+    CpuSection::Update,
+    CpuSection::Physics,
+    CpuSection::Render,
+    CpuSection::SurfaceWait,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      CpuSection::Input => "input",
+      CpuSection::Physics => "physics",
+      CpuSection::Update => "update",
+      CpuSection::Render => "render encode",
+      CpuSection::SurfaceWait => "surface wait",
+    }
+  }
+
+  fn index(self) -> usize {
+    self as usize
+  }
+}
+
+/// Frame, CPU and GPU timings, all in milliseconds. The engine records them every frame and
+/// the renderer draws them as an overlay while `show_overlay` is set.
+pub struct Profiler {
+  pub show_overlay: bool,
+  /// the last `HISTORY_FRAMES` frame times, oldest first
+  frame_times: VecDeque<f32>,
+  frame_time: f32,
+  /// indexed by `CpuSection::index`
+  cpu_times: [f32; 5],
+  /// time recorded with `record_within` since the last lap, left out of the next one
+  nested_time: f32,
+  /// `None` when the device can't time passes
+  gpu_times: Option<Vec<(&'static str, f32)>>,
+  lap_start: instant::Instant,
+}
+
+impl Profiler {
+  pub fn new() -> Self {
+    Self {
+      show_overlay: false,
+      frame_times: VecDeque::with_capacity(HISTORY_FRAMES),
+      frame_time: 0.0,
+      cpu_times: [0.0; 5],
+      nested_time: 0.0,
+      gpu_times: None,
+      lap_start: instant::Instant::now(),
+    }
+  }
+
+  /// Records the length of the last frame and starts timing the first section of this one.
+  pub fn begin_frame(&mut self, delta_time: f32) {
+    let frame_time = delta_time * 1000.0;
+    if self.frame_times.is_empty() {
+      self.frame_time = frame_time;
+    }
+    if self.frame_times.len() == HISTORY_FRAMES {
+      self.frame_times.pop_front();
+    }
+    self.frame_times.push_back(frame_time);
+    self.frame_time = smooth(self.frame_time, frame_time);
+    self.lap_start = instant::Instant::now();
+    self.nested_time = 0.0;
+  }
+
+  /// Ends `section`, which ran since the previous lap or the start of the frame, minus the
+  /// time recorded with `record_within` in between.
+  pub fn lap(&mut self, section: CpuSection) {
+    let now = instant::Instant::now();
+    let time = (now - self.lap_start).as_secs_f32() * 1000.0 - self.nested_time;
+    self.record(section, time.max(0.0));
+    self.lap_start = now;
+    self.nested_time = 0.0;
+  }
+
+  /// Records `time` spent in `section` while another section's lap was running, for work
+  /// that is timed where it happens instead of between laps.
+  pub fn record_within(&mut self, section: CpuSection, time: Duration) {
+    let time = time.as_secs_f32() * 1000.0;
+    self.record(section, time);
+    self.nested_time += time;
+  }
+
+  fn record(&mut self, section: CpuSection, time: f32) {
+    let smoothed = &mut self.cpu_times[section.index()];
+    *smoothed = smooth(*smoothed, time);
+  }
+
+  /// Takes the latest GPU pass timings, `None` when timestamp queries are unsupported.
+  pub fn record_gpu(&mut self, timings: Option<&[(&'static str, f32)]>) {
+    let Some(timings) = timings else {
+      self.gpu_times = None;
+      return;
+    };
+
+    let gpu_times = self.gpu_times.get_or_insert_with(Vec::new);
+    for &(name, time) in timings {
+      match gpu_times.iter_mut().find(|(pass, _)| *pass == name) {
+        Some((_, smoothed)) => *smoothed = smooth(*smoothed, time),
+        None => gpu_times.push((name, time)),
+      }
+    }
+  }
+
+  /// Frames per second, smoothed so it can be read.
+  pub fn fps(&self) -> f32 {
+    if self.frame_time > 0.0 {
+      1000.0 / self.frame_time
+    } else {
+      0.0
+    }
+  }
+
+  pub fn frame_time(&self) -> f32 {
+    self.frame_time
+  }
+
+  pub fn frame_times(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+    self.frame_times.iter().copied()
+  }
+
+  pub fn cpu_time(&self, section: CpuSection) -> f32 {
+    self.cpu_times[section.index()]
+  }
+
+  pub fn gpu_times(&self) -> Option<&[(&'static str, f32)]> {
+    self.gpu_times.as_deref()
+  }
+}
+
+impl Default for Profiler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn smooth(value: f32, sample: f32) -> f32 {
+  value + (sample - value) * SMOOTHING
+}
@@ -1,32 +1,72 @@
-use cgmath::{prelude::*, Vector3};
+use std::ops::Range;
+
+use cgmath::prelude::*;
 use wgpu::{util::DeviceExt, PresentMode};
 use winit::window::Window;
 
-use crate::engine::camera;
-use light::Light;
-use mesh::{DrawModel, Vertex};
+use crate::engine::{camera, settings::VideoSettings, GameState};
+use frustum::Frustum;
+use gpu_timer::GpuTimer;
+use light::LightState;
+use material::MaterialRaw;
+use mesh::{DrawModel, LodMesh, Shape3d, Vertex};
+use mesh_4d::{GpuMesh4d, Instance4dRaw, Shape4d, View4d};
+use outline::{Highlight, OutlineState};
+use post_process::PostProcess;
+use profiler_overlay::ProfilerOverlay;
+use render_queue::{FrameStats, RenderQueue};
+use screenshot::Screenshot;
+use shadow::ShadowState;
+use sky::SkyState;
+use targets::RenderTargets;
 
 use self::{
   color::Color,
   ui::{TextObject, UIState},
 };
 
-use super::physics::game_object::GameObject;
+use super::game_object::GameObject;
 
 pub mod color;
-mod light;
-mod mesh;
-mod resources;
+mod frustum;
+mod gpu_timer;
+pub mod light;
+pub mod material;
+pub mod mesh;
+pub mod mesh_4d;
+pub mod outline;
+pub mod post_process;
+mod profiler_overlay;
+pub mod render_queue;
+pub mod resources;
+pub mod screenshot;
+pub mod shadow;
+pub mod sky;
+mod targets;
 mod texture;
 pub mod ui;
 
-const MAX_INSTANCES: u64 = 100;
+/// 4D instances the buffer holds before it first has to grow
+const INITIAL_INSTANCES_4D: usize = 32;
+/// segments and rings of each sphere LOD and the distance, in bounding radii, up to which it is
+/// used
+const SPHERE_LODS: [(u32, u32, f32); 3] = [(32, 16, 12.0), (16, 8, 40.0), (8, 4, f32::INFINITY)];
+/// how far `skrungle.obj` is stretched along w
+const SKRUNGLE_DEPTH: f32 = 1.0;
+/// fraction of the draw distance where fog starts
+const FOG_START: f32 = 0.6;
+/// format of the texture a headless `State` renders into
+const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const MIN_RENDER_SCALE: f32 = 0.5;
+const MAX_RENDER_SCALE: f32 = 2.0;
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct CameraUniform {
   view_position: [f32; 4],
   view_proj: [[f32; 4]; 4],
+  fog_color: [f32; 4],
+  fog_range: [f32; 4],
 }
 
 impl CameraUniform {
@@ -34,6 +74,8 @@ impl CameraUniform {
     Self {
       view_position: [0.0; 4],
       view_proj: cgmath::Matrix4::identity().into(),
+      fog_color: [0.0; 4],
+      fog_range: [0.0; 4],
     }
   }
 
@@ -41,6 +83,21 @@ impl CameraUniform {
     self.view_position = camera.position.to_homogeneous().into();
     self.view_proj = (projection.calc_matrix() * camera.calc_matrix()).into()
   }
+
+  fn update_fog(&mut self, color: [f32; 3], settings: &VideoSettings) {
+    self.fog_color = [
+      color[0],
+      color[1],
+      color[2],
+      if settings.fog { 1.0 } else { 0.0 },
+    ];
+    self.fog_range = [
+      settings.draw_distance * FOG_START,
+      settings.draw_distance,
+      0.0,
+      0.0,
+    ];
+  }
 }
 
 #[repr(C)]
@@ -48,11 +105,11 @@ impl CameraUniform {
 struct InstanceRaw {
   model: [[f32; 4]; 4],
   normal: [[f32; 3]; 3],
-  color: [f32; 3],
+  material: MaterialRaw,
 }
 
 impl InstanceRaw {
-  pub fn from_game_object(game_object: &&mut GameObject) -> Self {
+  pub fn from_game_object(game_object: &GameObject) -> Self {
     use cgmath::{Deg, Quaternion};
     let amount_x = Quaternion::from_angle_x(Deg(game_object.transform.rotation.x));
     let amount_y = Quaternion::from_angle_y(Deg(game_object.transform.rotation.y));
@@ -69,7 +126,7 @@ impl InstanceRaw {
     Self {
       model: model.into(),
       normal: cgmath::Matrix3::from(rotation).into(),
-      color: game_object.color,
+      material: game_object.material.to_raw(),
     }
   }
 }
@@ -119,36 +176,113 @@ impl mesh::Vertex for InstanceRaw {
         wgpu::VertexAttribute {
           offset: mem::size_of::<[f32; 25]>() as wgpu::BufferAddress,
           shader_location: 12,
-          format: wgpu::VertexFormat::Float32x3,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 29]>() as wgpu::BufferAddress,
+          shader_location: 13,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 33]>() as wgpu::BufferAddress,
+          shader_location: 14,
+          format: wgpu::VertexFormat::Float32x4,
         },
       ],
     }
   }
 }
 
+/// Where finished frames go.
+enum Output {
+  Window {
+    surface: wgpu::Surface,
+    window: Window,
+  },
+  /// headless rendering, frames are read back with `State::capture_frame`
+  Texture(wgpu::Texture),
+}
+
+/// A frame being drawn, presented once everything has been submitted.
+enum Frame<'a> {
+  Surface(wgpu::SurfaceTexture),
+  Texture(&'a wgpu::Texture),
+  /// drawn only to be captured, when the surface texture can't be copied from
+  Offscreen(wgpu::Texture),
+}
+
+impl Output {
+  fn next_frame(&self) -> Result<Frame<'_>, wgpu::SurfaceError> {
+    Ok(match self {
+      Output::Window { surface, .. } => Frame::Surface(surface.get_current_texture()?),
+      Output::Texture(texture) => Frame::Texture(texture),
+    })
+  }
+}
+
+impl Frame<'_> {
+  fn texture(&self) -> &wgpu::Texture {
+    match self {
+      Frame::Surface(output) => &output.texture,
+      Frame::Texture(texture) => texture,
+      Frame::Offscreen(texture) => texture,
+    }
+  }
+
+  fn present(self) {
+    if let Frame::Surface(output) = self {
+      output.present();
+    }
+  }
+}
+
 pub struct State {
-  surface: wgpu::Surface,
+  output: Output,
   device: wgpu::Device,
   queue: wgpu::Queue,
   config: wgpu::SurfaceConfiguration,
   size: winit::dpi::PhysicalSize<u32>,
   render_pipeline: wgpu::RenderPipeline,
+  pipelines_4d: Pipelines4d,
+  /// alpha blended versions of the scene pipelines, without depth writes
+  transparent_pipeline: wgpu::RenderPipeline,
+  transparent_pipelines_4d: Pipelines4d,
+  render_pipeline_layout: wgpu::PipelineLayout,
   projection: camera::Projection,
   camera_uniform: CameraUniform,
   camera_buffer: wgpu::Buffer,
   camera_bind_group: wgpu::BindGroup,
-  obj: mesh::Mesh,
-  obj_4: mesh::Mesh4d,
-  projected_4d: mesh::Mesh,
-  instance_buffer: wgpu::Buffer,
-  #[allow(unused)]
-  instance_buffer_4: wgpu::Buffer,
-  instance_count: u32,
+  /// grid texture used by materials with a texture mapping
+  surface_texture_bind_group: wgpu::BindGroup,
+  /// indexed by `Shape3d::index`
+  meshes: Vec<LodMesh>,
+  /// indexed by `Shape4d::index`
+  meshes_4d: Vec<GpuMesh4d>,
+  render_queue: RenderQueue,
+  instance_buffer_4d: wgpu::Buffer,
+  instance_capacity_4d: usize,
+  /// opaque instance ranges of `instance_buffer_4d`, grouped by shape and view
+  draws_4d: Vec<(Shape4d, View4d, Range<u32>)>,
+  /// visible transparent objects from back to front, their instances follow the opaque ones
+  transparent_draws: Vec<TransparentDraw>,
+  stats: FrameStats,
   clear_color: wgpu::Color,
-  depth_texture: texture::Texture,
-  light: Light,
+  targets: RenderTargets,
+  /// MSAA sample counts this adapter can render the scene with
+  sample_counts: Vec<u32>,
+  render_scale: f32,
+  screenshot_requested: bool,
+  /// how long the last `render` waited for the surface texture
+  surface_wait: std::time::Duration,
+  post_process: PostProcess,
+  /// `None` when the device has no timestamp queries
+  gpu_timer: Option<GpuTimer>,
+  profiler_overlay: ProfilerOverlay,
+  light: LightState,
+  outline: OutlineState,
+  sky: SkyState,
+  shadow: ShadowState,
   ui: UIState,
-  window: Window,
 }
 
 impl State {
@@ -171,17 +305,7 @@ impl State {
       .await
       .unwrap();
 
-    let (device, queue) = adapter
-      .request_device(
-        &wgpu::DeviceDescriptor {
-          features: wgpu::Features::empty(),
-          limits: wgpu::Limits::default(),
-          label: None,
-        },
-        None,
-      )
-      .await
-      .unwrap();
+    let (device, queue) = request_device(&adapter).await.unwrap();
 
     let surface_caps = surface.get_capabilities(&adapter);
     let surface_format = surface_caps
@@ -191,8 +315,12 @@ impl State {
       .filter(|f| f.is_srgb())
       .next()
       .unwrap_or(surface_caps.formats[0]);
+    // screenshots copy straight out of the surface texture when the surface allows it,
+    // otherwise that frame is drawn offscreen
+    let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+      | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
     let config = wgpu::SurfaceConfiguration {
-      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      usage,
       format: surface_format,
       width: size.width,
       height: size.height,
@@ -202,6 +330,84 @@ impl State {
     };
     surface.configure(&device, &config);
 
+    let sample_counts = targets::supported_sample_counts(&adapter, device.features());
+    Self::from_parts(
+      device,
+      queue,
+      config,
+      sample_counts,
+      targets::can_resolve_depth(&adapter),
+      Output::Window { surface, window },
+      camera,
+    )
+    .await
+  }
+
+  /// Renders into an offscreen texture instead of a window, for tests and tools. Falls back
+  /// to a software adapter (lavapipe, WARP) when there is no GPU.
+  pub async fn new_headless(
+    width: u32,
+    height: u32,
+    camera: &camera::Camera,
+  ) -> anyhow::Result<Self> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+      backends: wgpu::Backends::all(),
+      ..Default::default()
+    });
+
+    let mut adapter = None;
+    for force_fallback_adapter in [false, true] {
+      adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+          power_preference: wgpu::PowerPreference::default(),
+          compatible_surface: None,
+          force_fallback_adapter,
+        })
+        .await;
+      if adapter.is_some() {
+        break;
+      }
+    }
+    let adapter = adapter.ok_or_else(|| anyhow::anyhow!("no graphics adapter available"))?;
+    let (device, queue) = request_device(&adapter).await?;
+
+    let config = wgpu::SurfaceConfiguration {
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+      format: HEADLESS_FORMAT,
+      width,
+      height,
+      present_mode: PresentMode::AutoVsync,
+      alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+      view_formats: vec![],
+    };
+    let texture = create_output_texture(&device, &config);
+
+    let sample_counts = targets::supported_sample_counts(&adapter, device.features());
+    Ok(
+      Self::from_parts(
+        device,
+        queue,
+        config,
+        sample_counts,
+        targets::can_resolve_depth(&adapter),
+        Output::Texture(texture),
+        camera,
+      )
+      .await,
+    )
+  }
+
+  async fn from_parts(
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    sample_counts: Vec<u32>,
+    depth_resolvable: bool,
+    output: Output,
+    camera: &camera::Camera,
+  ) -> Self {
+    let size = winit::dpi::PhysicalSize::new(config.width, config.height);
+
     let projection =
       camera::Projection::new(config.width, config.height, cgmath::Deg(60.0), 0.1, 100.0);
 
@@ -238,89 +444,117 @@ impl State {
       }],
     });
 
-    let light = Light::new(
-      &device,
-      &camera_bind_group_layout,
-      wgpu::ShaderSource::Wgsl(include_str!("render/shader/light.wgsl").into()),
-      config.format,
-      [2.0, 5.0, 2.0],
-      [1.0, 1.0, 1.0],
-    );
+    let light = LightState::new(&device, &camera_bind_group_layout, 1);
+    let outline = OutlineState::new(&device, &camera_bind_group_layout, 1);
+    let sky = SkyState::new(&device, &queue, 1);
 
-    let obj = resources::load_mesh("cube.obj", &device).await.unwrap();
-    let obj_4 = resources::load_mesh_4d("skrungle.obj").await.unwrap();
+    let mut meshes = vec![];
+    for shape in Shape3d::ALL {
+      let mesh = match shape {
+        Shape3d::Cube => LodMesh::single(resources::load_mesh("cube.obj", &device).await.unwrap()),
+        Shape3d::Sphere => LodMesh::new(
+          SPHERE_LODS
+            .iter()
+            .map(|&(segments, rings, distance)| {
+              (mesh::Mesh::sphere(&device, segments, rings), distance)
+            })
+            .collect(),
+        ),
+      };
+      meshes.push(mesh);
+    }
+    let mut meshes_4d = vec![];
+    for shape in Shape4d::ALL {
+      let mesh = match shape {
+        Shape4d::Tesseract => mesh_4d::Mesh4d::tesseract(),
+        Shape4d::Skrungle => resources::load_mesh_4d("skrungle.obj", SKRUNGLE_DEPTH)
+          .await
+          .unwrap(),
+      };
+      meshes_4d.push(mesh.upload(&device));
+    }
 
-    let projected_4d = obj_4.project(&device);
+    let render_queue = RenderQueue::new(&device);
 
-    let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-      label: Some("Instance Buffer"),
-      size: (std::mem::size_of::<InstanceRaw>() as u64) * MAX_INSTANCES,
-      usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-      mapped_at_creation: false,
-    });
+    let instance_buffer_4d = create_instance_buffer_4d(&device, INITIAL_INSTANCES_4D);
 
-    let instances = vec![InstanceRaw {
-      model: cgmath::Matrix4::from_translation(Vector3::new(0.0, 4.0, 4.0)).into(),
-      normal: cgmath::Matrix3::from(cgmath::Quaternion::from_angle_x(cgmath::Deg(0.0))).into(),
-      color: [1.0, 0.0, 0.5],
-    }];
+    let clear_color = wgpu::Color::BLACK;
 
-    let instance_buffer_4 = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some("4D Instance Buffer"),
-      contents: bytemuck::cast_slice(&instances),
-      usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-    });
+    let targets = RenderTargets::new(&device, config.width, config.height, 1, depth_resolvable);
+    let post_process = PostProcess::new(&device, config.format, config.width, config.height);
 
-    let clear_color = wgpu::Color::BLACK;
+    let shadow = ShadowState::new(&device);
 
-    let depth_texture = texture::Texture::create_depth_texture(&device, &config, "depth_texture");
+    let surface_texture = resources::load_texture("grid.png", &device, &queue)
+      .await
+      .unwrap();
+    let surface_texture_bind_group_layout =
+      texture::Texture::bind_group_layout(&device, "surface_texture_bind_group_layout");
+    let surface_texture_bind_group = surface_texture.bind_group(
+      &device,
+      &surface_texture_bind_group_layout,
+      "surface_texture_bind_group",
+    );
 
     let render_pipeline_layout: wgpu::PipelineLayout =
       device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[&camera_bind_group_layout, &light.bind_group_layout],
+        bind_group_layouts: &[
+          &camera_bind_group_layout,
+          &light.bind_group_layout,
+          &shadow.bind_group_layout,
+          &surface_texture_bind_group_layout,
+        ],
         push_constant_ranges: &[],
       });
 
-    let render_pipeline = {
-      let shader = wgpu::ShaderModuleDescriptor {
-        label: Some("Normal Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("render/shader/shader.wgsl").into()),
-      };
-      create_render_pipeline(
-        &device,
-        &render_pipeline_layout,
-        config.format,
-        Some(texture::Texture::DEPTH_FORMAT),
-        &[mesh::MeshVertex::desc(), InstanceRaw::desc()],
-        shader,
-      )
-    };
+    let (render_pipeline, pipelines_4d) =
+      create_scene_pipelines(&device, &render_pipeline_layout, 1, false);
+    let (transparent_pipeline, transparent_pipelines_4d) =
+      create_scene_pipelines(&device, &render_pipeline_layout, 1, true);
 
     let ui = UIState::new(&device, &config);
+    let gpu_timer = GpuTimer::new(&device, &queue);
+    let profiler_overlay = ProfilerOverlay::new(&device, config.format);
 
     Self {
-      surface,
+      output,
       device,
       queue,
       config,
       size,
       render_pipeline,
+      pipelines_4d,
+      transparent_pipeline,
+      transparent_pipelines_4d,
+      render_pipeline_layout,
       projection,
       camera_uniform,
       camera_buffer,
       camera_bind_group,
-      obj,
-      obj_4,
-      projected_4d,
-      instance_count: 0,
-      instance_buffer,
-      instance_buffer_4,
+      surface_texture_bind_group,
+      meshes,
+      meshes_4d,
+      render_queue,
+      draws_4d: vec![],
+      transparent_draws: vec![],
+      stats: FrameStats::default(),
+      instance_buffer_4d,
+      instance_capacity_4d: INITIAL_INSTANCES_4D,
       clear_color,
-      depth_texture,
+      targets,
+      sample_counts,
+      render_scale: 1.0,
+      screenshot_requested: false,
+      surface_wait: std::time::Duration::ZERO,
+      post_process,
+      gpu_timer,
+      profiler_overlay,
       light,
+      outline,
+      sky,
+      shadow,
       ui,
-      window,
     }
   }
 
@@ -331,26 +565,93 @@ impl State {
       self.config.width = new_size.width;
       self.config.height = new_size.height;
 
-      self.surface.configure(&self.device, &self.config);
-      self.depth_texture =
-        texture::Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
+      match &mut self.output {
+        Output::Window { surface, .. } => surface.configure(&self.device, &self.config),
+        Output::Texture(texture) => *texture = create_output_texture(&self.device, &self.config),
+      }
+      self.resize_targets(self.targets.sample_count);
     }
   }
 
+  /// Rebuilds the offscreen targets and pipelines when the MSAA or render scale settings change.
+  fn apply_video_settings(&mut self, settings: &VideoSettings) {
+    let sample_count = self
+      .sample_counts
+      .iter()
+      .copied()
+      .filter(|&count| count <= settings.msaa_samples)
+      .max()
+      .unwrap_or(1);
+    let render_scale = settings
+      .render_scale
+      .clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+
+    if sample_count != self.targets.sample_count {
+      (self.render_pipeline, self.pipelines_4d) = create_scene_pipelines(
+        &self.device,
+        &self.render_pipeline_layout,
+        sample_count,
+        false,
+      );
+      (self.transparent_pipeline, self.transparent_pipelines_4d) = create_scene_pipelines(
+        &self.device,
+        &self.render_pipeline_layout,
+        sample_count,
+        true,
+      );
+      self.light.set_sample_count(&self.device, sample_count);
+      self.outline.set_sample_count(&self.device, sample_count);
+      self.sky.set_sample_count(&self.device, sample_count);
+    }
+
+    if sample_count != self.targets.sample_count || render_scale != self.render_scale {
+      self.render_scale = render_scale;
+      self.resize_targets(sample_count);
+    }
+  }
+
+  fn resize_targets(&mut self, sample_count: u32) {
+    let max_size = self.device.limits().max_texture_dimension_2d;
+    let scale = |size: u32| ((size as f32 * self.render_scale).round() as u32).clamp(1, max_size);
+    let (width, height) = (scale(self.config.width), scale(self.config.height));
+
+    self
+      .targets
+      .resize(&self.device, width, height, sample_count);
+    self.post_process.resize(&self.device, width, height);
+  }
+
   pub fn update_clear_color(&mut self, color: &Color) {
     self.clear_color = color.to_wgpu();
   }
 
   pub fn update(
     &mut self,
-    camera: &camera::Camera,
-    dt: f32,
+    game: &GameState,
     objects: Vec<&mut GameObject>,
     text_objects: Vec<&TextObject>,
   ) {
+    self.apply_video_settings(&game.settings.video);
+
+    self.projection.set_fovy(game.camera.fovy);
+    self.projection.set_zfar(game.settings.video.draw_distance);
     self
       .camera_uniform
-      .update_view_proj(camera, &self.projection);
+      .update_view_proj(&game.camera, &self.projection);
+    let fog_color = self.sky.update(
+      &self.device,
+      &self.queue,
+      &game.sky,
+      &self.camera_uniform,
+      [
+        self.clear_color.r as f32,
+        self.clear_color.g as f32,
+        self.clear_color.b as f32,
+      ],
+    );
+    self
+      .camera_uniform
+      .update_fog(fog_color, &game.settings.video);
 
     self.queue.write_buffer(
       &self.camera_buffer,
@@ -358,39 +659,175 @@ impl State {
       bytemuck::cast_slice(&[self.camera_uniform]),
     );
 
-    let instance_data = objects
+    // culled objects are still queued after the visible ones so they can cast shadows
+    let frustum = Frustum::from_matrix(self.camera_uniform.view_proj.into());
+    let in_view = |object: &&GameObject| {
+      let (center, radius) = object.transform.bounding_sphere();
+      frustum.contains_sphere(center, radius)
+    };
+    let (transparent, opaque): (Vec<&GameObject>, Vec<_>) = objects
       .iter()
-      .map(InstanceRaw::from_game_object)
+      .map(|object| &**object)
+      .partition(|object| object.material.is_transparent());
+    let camera_position = game.camera.position.to_vec();
+    self
+      .render_queue
+      .build(&opaque, &self.meshes, &frustum, camera_position);
+
+    let highlighted = objects
+      .iter()
+      .map(|object| &**object)
+      .filter(|object| object.highlight != Highlight::None && in_view(object))
       .collect::<Vec<_>>();
+    self
+      .outline
+      .update(&self.queue, &highlighted, &game.outline);
 
-    self.queue.write_buffer(
-      &self.instance_buffer,
-      0,
-      bytemuck::cast_slice(&instance_data),
+    let mut instance_data_4d = self.update_4d(&game.objects_4d, game.camera.w);
+
+    let transparent = transparent
+      .into_iter()
+      .filter(in_view)
+      .map(Transparent::Mesh)
+      .chain(
+        game
+          .objects_4d
+          .iter()
+          .filter(|object| object.material.is_transparent())
+          .map(Transparent::Object4d),
+      )
+      .collect::<Vec<_>>();
+    self.update_transparent(
+      transparent,
+      camera_position,
+      game.camera.w,
+      &mut instance_data_4d,
     );
-    self.instance_count = instance_data.len() as u32;
 
-    let old_position: cgmath::Vector3<_> = self.light.uniform.position.into();
-    self.light.uniform.position =
-      (cgmath::Quaternion::from_axis_angle((0.0, 1.0, 0.0).into(), cgmath::Deg(60.0 * dt))
-        * old_position)
-        .into();
+    self.render_queue.upload(&self.device, &self.queue);
+    if instance_data_4d.len() > self.instance_capacity_4d {
+      self.instance_capacity_4d = instance_data_4d.len().next_power_of_two();
+      self.instance_buffer_4d = create_instance_buffer_4d(&self.device, self.instance_capacity_4d);
+    }
     self.queue.write_buffer(
-      &self.light.buffer,
+      &self.instance_buffer_4d,
       0,
-      bytemuck::cast_slice(&[self.light.uniform]),
+      bytemuck::cast_slice(&instance_data_4d),
     );
 
-    self.obj_4.angle += 2.0;
+    self.light.update(&self.device, &self.queue, &game.lights);
+    self.shadow.update(
+      &self.queue,
+      game.camera.position,
+      &game.lights,
+      game.blob_shadow.as_ref(),
+    );
+    self.post_process.update(&self.queue, game);
 
-    self.ui.draw_text(text_objects);
+    self.profiler_overlay.update(
+      &self.queue,
+      &game.profiler,
+      &self.stats,
+      self.config.width,
+      self.config.height,
+    );
+    self.ui.draw_text(
+      text_objects
+        .into_iter()
+        .chain([&self.profiler_overlay.text])
+        .collect(),
+    );
+  }
+
+  /// Groups the opaque 4D instances by shape and view so each group is a single draw.
+  fn update_4d(&mut self, objects: &[mesh_4d::Object4d], camera_w: f32) -> Vec<Instance4dRaw> {
+    let mut instance_data = Vec::with_capacity(objects.len());
+    self.draws_4d.clear();
+    for shape in Shape4d::ALL {
+      for view in View4d::ALL {
+        let start = instance_data.len() as u32;
+        instance_data.extend(
+          objects
+            .iter()
+            .filter(|object| {
+              object.shape == shape && object.view == view && !object.material.is_transparent()
+            })
+            .map(|object| Instance4dRaw::from_object(object, camera_w)),
+        );
+        let end = instance_data.len() as u32;
+        if end > start {
+          self.draws_4d.push((shape, view, start..end));
+        }
+      }
+    }
+    instance_data
+  }
+
+  /// Sorts the transparent objects back to front and appends their instances after the opaque
+  /// ones. Neighbouring regular objects with the same mesh share a draw.
+  fn update_transparent(
+    &mut self,
+    mut objects: Vec<Transparent>,
+    camera_position: cgmath::Vector3<f32>,
+    camera_w: f32,
+    instance_data_4d: &mut Vec<Instance4dRaw>,
+  ) {
+    let distance = |object: &Transparent| {
+      let position = match object {
+        Transparent::Mesh(object) => object.transform.position,
+        Transparent::Object4d(object) => object.position,
+      };
+      (position - camera_position).magnitude2()
+    };
+    objects.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+    self.transparent_draws.clear();
+    for object in objects {
+      match object {
+        Transparent::Mesh(object) => {
+          let shape = object.shape;
+          let lod = render_queue::select_lod(&self.meshes, object, camera_position);
+          let index = self.render_queue.push(object);
+          match self.transparent_draws.last_mut() {
+            Some(TransparentDraw::Meshes(last_shape, last_lod, instances))
+              if (*last_shape, *last_lod) == (shape, lod) =>
+            {
+              instances.end = index + 1
+            }
+            _ => self
+              .transparent_draws
+              .push(TransparentDraw::Meshes(shape, lod, index..index + 1)),
+          }
+        }
+        Transparent::Object4d(object) => {
+          let index = instance_data_4d.len() as u32;
+          instance_data_4d.push(Instance4dRaw::from_object(object, camera_w));
+          self
+            .transparent_draws
+            .push(TransparentDraw::Object4d(object.shape, object.view, index));
+        }
+      }
+    }
   }
 
   pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-    let output = self.surface.get_current_texture()?;
+    let copy_src = wgpu::TextureUsages::COPY_SRC;
+    self.surface_wait = std::time::Duration::ZERO;
+    let frame = if self.screenshot_requested && !self.config.usage.contains(copy_src) {
+      let config = wgpu::SurfaceConfiguration {
+        usage: self.config.usage | copy_src,
+        ..self.config.clone()
+      };
+      Frame::Offscreen(create_output_texture(&self.device, &config))
+    } else {
+      let started = instant::Instant::now();
+      let frame = self.output.next_frame();
+      self.surface_wait = started.elapsed();
+      frame?
+    };
 
-    let view = output
-      .texture
+    let view = frame
+      .texture()
       .create_view(&wgpu::TextureViewDescriptor::default());
 
     let mut encoder = self
@@ -398,20 +835,33 @@ impl State {
       .create_command_encoder(&wgpu::CommandEncoderDescriptor {
         label: Some("Render Encoder"),
       });
+    if let Some(gpu_timer) = &mut self.gpu_timer {
+      gpu_timer.begin(&mut encoder);
+    }
+
+    let mut stats = FrameStats {
+      culled: self.render_queue.culled_count(),
+      ..Default::default()
+    };
+    self
+      .shadow
+      .render(&mut encoder, &self.meshes, &self.render_queue, &mut stats);
+    mark_gpu_pass(&mut self.gpu_timer, &mut encoder, "shadow");
 
     {
+      let (color_view, resolve_target) = self.targets.color_attachment();
       let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Render Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-          view: &view,
-          resolve_target: None,
+          view: color_view,
+          resolve_target,
           ops: wgpu::Operations {
             load: wgpu::LoadOp::Clear(self.clear_color),
             store: wgpu::StoreOp::Store,
           },
         })],
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-          view: &self.depth_texture.view,
+          view: self.targets.depth_attachment(),
           depth_ops: Some(wgpu::Operations {
             load: wgpu::LoadOp::Clear(1.0),
             store: wgpu::StoreOp::Store,
@@ -421,43 +871,151 @@ impl State {
         occlusion_query_set: None,
         timestamp_writes: None,
       });
-      render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
-
-      self.projected_4d = self.obj_4.project(&self.device);
+      render_pass.set_vertex_buffer(1, self.render_queue.buffer.slice(..));
 
       use mesh::DrawLight;
       render_pass.set_pipeline(&self.light.render_pipeline);
-      render_pass.draw_light_mesh(&self.obj, &self.camera_bind_group, &self.light.bind_group);
-      render_pass.draw_light_mesh(&self.obj, &self.camera_bind_group, &self.light.bind_group);
-
-      render_pass.set_pipeline(&self.render_pipeline);
-      render_pass.draw_mesh_instanced(
-        &self.obj,
-        0..self.instance_count,
+      render_pass.draw_light_mesh_instanced(
+        self.meshes[Shape3d::Cube.index()].get(0),
+        0..self.light.count,
         &self.camera_bind_group,
         &self.light.bind_group,
       );
-      // render_pass.set_vertex_buffer(1, self.instance_buffer_4.slice(..));
-      // render_pass.draw_mesh_instanced(
-      //   &self.projected_4d,
-      //   0..1,
-      //   &self.camera_bind_group,
-      //   &self.light.bind_group,
-      // );
+
+      render_pass.set_pipeline(&self.render_pipeline);
+      render_pass.set_bind_group(2, &self.shadow.bind_group, &[]);
+      render_pass.set_bind_group(3, &self.surface_texture_bind_group, &[]);
+      for batch in self.render_queue.visible() {
+        let mesh = self.meshes[batch.key.shape.index()].get(batch.key.lod);
+        render_pass.draw_mesh_instanced(
+          mesh,
+          batch.instances.clone(),
+          &self.camera_bind_group,
+          &self.light.bind_group,
+        );
+        stats.record(batch.instances.len() as u32, mesh.num_elements / 3);
+      }
+
+      render_pass.set_vertex_buffer(1, self.instance_buffer_4d.slice(..));
+      for (shape, view, instances) in &self.draws_4d {
+        let (vertex_buffer, num_vertices) = self.meshes_4d[shape.index()].vertices(*view);
+        render_pass.set_pipeline(self.pipelines_4d.get(*view));
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..num_vertices, instances.clone());
+        stats.record(instances.len() as u32, num_vertices / 3);
+      }
+
+      self
+        .outline
+        .draw(&mut render_pass, &self.meshes, &self.camera_bind_group);
+
+      self.sky.draw(&mut render_pass);
+
+      // transparent objects go last so they blend over everything opaque behind them
+      for draw in &self.transparent_draws {
+        match draw {
+          TransparentDraw::Meshes(shape, lod, instances) => {
+            let mesh = self.meshes[shape.index()].get(*lod);
+            render_pass.set_pipeline(&self.transparent_pipeline);
+            render_pass.set_vertex_buffer(1, self.render_queue.buffer.slice(..));
+            render_pass.draw_mesh_instanced(
+              mesh,
+              instances.clone(),
+              &self.camera_bind_group,
+              &self.light.bind_group,
+            );
+            stats.record(instances.len() as u32, mesh.num_elements / 3);
+          }
+          TransparentDraw::Object4d(shape, view, instance) => {
+            let (vertex_buffer, num_vertices) = self.meshes_4d[shape.index()].vertices(*view);
+            render_pass.set_pipeline(self.transparent_pipelines_4d.get(*view));
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer_4d.slice(..));
+            render_pass.draw(0..num_vertices, *instance..*instance + 1);
+            stats.record(1, num_vertices / 3);
+          }
+        }
+      }
     }
+    self.stats = stats;
+
+    self.targets.resolve_depth(&mut encoder);
+    mark_gpu_pass(&mut self.gpu_timer, &mut encoder, "scene");
+
+    self.post_process.render(
+      &self.device,
+      &mut encoder,
+      &self.targets.color,
+      &self.targets.depth,
+      &view,
+    );
+    mark_gpu_pass(&mut self.gpu_timer, &mut encoder, "post process");
 
+    self.profiler_overlay.render(&mut encoder, &view);
     self
       .ui
       .render(&self.device, &mut encoder, &view, &self.config);
+    mark_gpu_pass(&mut self.gpu_timer, &mut encoder, "ui");
 
+    if let Some(gpu_timer) = &mut self.gpu_timer {
+      gpu_timer.finish(&mut encoder);
+    }
     self.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
+    if let Some(gpu_timer) = &mut self.gpu_timer {
+      gpu_timer.read_back(&self.device);
+    }
+
+    if self.screenshot_requested {
+      self.screenshot_requested = false;
+      match Screenshot::capture(&self.device, &self.queue, frame.texture()).and_then(|screenshot| {
+        screenshot.save_timestamped(std::path::Path::new(screenshot::SCREENSHOT_DIRECTORY))
+      }) {
+        Ok(path) => eprintln!("saved screenshot to {}", path.display()),
+        Err(e) => eprintln!("failed to save screenshot: {:?}", e),
+      }
+    }
+
+    frame.present();
 
     Ok(())
   }
 
+  /// How long the passes of a recent frame took on the GPU, in milliseconds. `None` when the
+  /// device can't time them.
+  pub fn gpu_timings(&self) -> Option<&[(&'static str, f32)]> {
+    self.gpu_timer.as_ref().map(|gpu_timer| gpu_timer.timings())
+  }
+
+  /// Time the last frame spent waiting for the surface texture, part of `render` but not of
+  /// the work it does.
+  pub fn surface_wait(&self) -> std::time::Duration {
+    self.surface_wait
+  }
+
+  /// Draw calls and instances of the last rendered frame.
+  pub fn frame_stats(&self) -> FrameStats {
+    self.stats
+  }
+
+  /// Saves the next rendered frame as a PNG in the screenshots directory.
+  pub fn request_screenshot(&mut self) {
+    self.screenshot_requested = true;
+  }
+
+  /// Renders a frame and reads it back, only available on a headless `State`.
+  pub fn capture_frame(&mut self) -> anyhow::Result<Screenshot> {
+    self.render()?;
+    match &self.output {
+      Output::Texture(texture) => Screenshot::capture(&self.device, &self.queue, texture),
+      Output::Window { .. } => anyhow::bail!("capture_frame needs a headless renderer"),
+    }
+  }
+
   pub fn window(&self) -> &Window {
-    &self.window
+    match &self.output {
+      Output::Window { window, .. } => window,
+      Output::Texture(_) => panic!("a headless renderer has no window"),
+    }
   }
 
   pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
@@ -465,6 +1023,63 @@ impl State {
   }
 }
 
+fn mark_gpu_pass(
+  gpu_timer: &mut Option<GpuTimer>,
+  encoder: &mut wgpu::CommandEncoder,
+  name: &'static str,
+) {
+  if let Some(gpu_timer) = gpu_timer {
+    gpu_timer.mark(encoder, name);
+  }
+}
+
+async fn request_device(
+  adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+  adapter
+    .request_device(
+      &wgpu::DeviceDescriptor {
+        features: adapter.features()
+          & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | wgpu::Features::TIMESTAMP_QUERY),
+        limits: wgpu::Limits::default(),
+        label: None,
+      },
+      None,
+    )
+    .await
+}
+
+fn create_instance_buffer_4d(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+  device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("4D Instance Buffer"),
+    size: (std::mem::size_of::<Instance4dRaw>() * capacity) as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
+}
+
+fn create_output_texture(
+  device: &wgpu::Device,
+  config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+  device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("output_texture"),
+    size: wgpu::Extent3d {
+      width: config.width,
+      height: config.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: config.format,
+    usage: config.usage,
+    view_formats: &[],
+  })
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_render_pipeline(
   device: &wgpu::Device,
   layout: &wgpu::PipelineLayout,
@@ -472,8 +1087,17 @@ fn create_render_pipeline(
   depth_format: Option<wgpu::TextureFormat>,
   vertex_layouts: &[wgpu::VertexBufferLayout],
   shader: wgpu::ShaderModuleDescriptor,
+  cull_mode: Option<wgpu::Face>,
+  transparent: bool,
+  sample_count: u32,
 ) -> wgpu::RenderPipeline {
   let shader = device.create_shader_module(shader);
+  // transparent surfaces blend over what is behind them and must not hide each other
+  let blend = if transparent {
+    wgpu::BlendState::ALPHA_BLENDING
+  } else {
+    wgpu::BlendState::REPLACE
+  };
 
   device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
     label: Some("Render Pipeline"),
@@ -488,10 +1112,7 @@ fn create_render_pipeline(
       entry_point: "fs_main",
       targets: &[Some(wgpu::ColorTargetState {
         format: color_format,
-        blend: Some(wgpu::BlendState {
-          alpha: wgpu::BlendComponent::REPLACE,
-          color: wgpu::BlendComponent::REPLACE,
-        }),
+        blend: Some(blend),
         write_mask: wgpu::ColorWrites::ALL,
       })],
     }),
@@ -499,23 +1120,115 @@ fn create_render_pipeline(
       topology: wgpu::PrimitiveTopology::TriangleList,
       strip_index_format: None,
       front_face: wgpu::FrontFace::Ccw,
-      cull_mode: Some(wgpu::Face::Back),
+      cull_mode,
       polygon_mode: wgpu::PolygonMode::Fill,
       unclipped_depth: false,
       conservative: false,
     },
     depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
       format,
-      depth_write_enabled: true,
+      depth_write_enabled: !transparent,
       depth_compare: wgpu::CompareFunction::Less,
       stencil: wgpu::StencilState::default(),
       bias: wgpu::DepthBiasState::default(),
     }),
     multisample: wgpu::MultisampleState {
-      count: 1,
+      count: sample_count,
       mask: !0,
       alpha_to_coverage_enabled: false,
     },
     multiview: None,
   })
 }
+
+/// Object drawn in the transparent pass, see `State::update_transparent`.
+enum Transparent<'a> {
+  Mesh(&'a GameObject),
+  Object4d(&'a mesh_4d::Object4d),
+}
+
+enum TransparentDraw {
+  /// range of the render queue's buffer, drawn with the given shape and LOD
+  Meshes(Shape3d, usize, Range<u32>),
+  /// single instance of `instance_buffer_4d`
+  Object4d(Shape4d, View4d, u32),
+}
+
+struct Pipelines4d {
+  projection: wgpu::RenderPipeline,
+  slice: wgpu::RenderPipeline,
+}
+
+impl Pipelines4d {
+  fn get(&self, view: View4d) -> &wgpu::RenderPipeline {
+    match view {
+      View4d::Projection => &self.projection,
+      View4d::Slice => &self.slice,
+    }
+  }
+}
+
+/// Pipelines for regular and 4D objects, all lit by `lighting.wgsl`.
+fn create_scene_pipelines(
+  device: &wgpu::Device,
+  layout: &wgpu::PipelineLayout,
+  sample_count: u32,
+  transparent: bool,
+) -> (wgpu::RenderPipeline, Pipelines4d) {
+  let lit_shader = |label, sources: &[&str]| wgpu::ShaderModuleDescriptor {
+    label: Some(label),
+    source: wgpu::ShaderSource::Wgsl(
+      format!(
+        "{}{}",
+        include_str!("render/shader/lighting.wgsl"),
+        sources.concat()
+      )
+      .into(),
+    ),
+  };
+  let pipeline = |shader, vertex_layouts: &[wgpu::VertexBufferLayout], cull_mode| {
+    create_render_pipeline(
+      device,
+      layout,
+      texture::Texture::HDR_FORMAT,
+      Some(texture::Texture::DEPTH_FORMAT),
+      vertex_layouts,
+      shader,
+      cull_mode,
+      transparent,
+      sample_count,
+    )
+  };
+
+  let render_pipeline = pipeline(
+    lit_shader(
+      "Normal Shader",
+      &[include_str!("render/shader/shader.wgsl")],
+    ),
+    &[mesh::MeshVertex::desc(), InstanceRaw::desc()],
+    Some(wgpu::Face::Back),
+  );
+
+  // the winding of 4D faces flips as they rotate through w, so draw both sides
+  let shader_4d = include_str!("render/shader/shader_4d.wgsl");
+  let pipelines_4d = Pipelines4d {
+    projection: pipeline(
+      lit_shader(
+        "4D Projection Shader",
+        &[shader_4d, include_str!("render/shader/project_4d.wgsl")],
+      ),
+      &[mesh_4d::Vertex4d::desc(), Instance4dRaw::desc()],
+      None,
+    ),
+    slice: pipeline(
+      lit_shader(
+        "4D Slice Shader",
+        &[shader_4d, include_str!("render/shader/slice_4d.wgsl")],
+      ),
+      &[mesh_4d::CellVertex::desc(), Instance4dRaw::desc()],
+      None,
+    ),
+  };
+
+  (render_pipeline, pipelines_4d)
+}
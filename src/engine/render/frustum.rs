@@ -0,0 +1,32 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+/// The six planes of a view-projection matrix, used to skip objects that can't be seen.
+pub struct Frustum {
+  /// xyz is the inward facing normal, w the distance from the origin
+  planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+  /// Extracts the planes from a matrix that maps depth to wgpu's 0..1 range.
+  pub fn from_matrix(view_proj: Matrix4<f32>) -> Self {
+    let row = |i| view_proj.row(i);
+    let planes = [
+      row(3) + row(0),
+      row(3) - row(0),
+      row(3) + row(1),
+      row(3) - row(1),
+      row(2),
+      row(3) - row(2),
+    ]
+    .map(|plane| plane / plane.truncate().magnitude());
+
+    Self { planes }
+  }
+
+  pub fn contains_sphere(&self, center: Vector3<f32>, radius: f32) -> bool {
+    self
+      .planes
+      .iter()
+      .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+  }
+}
@@ -0,0 +1,150 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+/// one more than the passes a frame can be split into
+const MAX_TIMESTAMPS: u32 = 8;
+
+/// Times the passes of a frame with timestamp queries written between them. Results are read
+/// back without waiting, frames recorded while the previous ones are still being read are not
+/// timed.
+///
+/// The timestamps go on the encoder instead of into each pass's `timestamp_writes`, since
+/// not every pass is begun here: every post process effect begins its own, and the UI text
+/// pass is begun inside `wgpu_glyph`, which has no way to hand it timestamp writes.
+pub struct GpuTimer {
+  query_set: wgpu::QuerySet,
+  resolve_buffer: wgpu::Buffer,
+  read_buffer: wgpu::Buffer,
+  /// nanoseconds per timestamp tick
+  period: f32,
+  /// passes timed in the frame being encoded, each one ends with a timestamp
+  passes: Vec<&'static str>,
+  recording: bool,
+  /// passes of the frame being read back
+  pending_passes: Vec<&'static str>,
+  pending: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+  /// in milliseconds, from the last frame that was read back
+  timings: Vec<(&'static str, f32)>,
+}
+
+impl GpuTimer {
+  /// `None` when the device was created without timestamp queries.
+  pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+    if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+      return None;
+    }
+
+    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+      label: Some("GPU Timer Queries"),
+      ty: wgpu::QueryType::Timestamp,
+      count: MAX_TIMESTAMPS,
+    });
+    let size = (MAX_TIMESTAMPS as usize * std::mem::size_of::<u64>()) as wgpu::BufferAddress;
+    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("GPU Timer Resolve Buffer"),
+      size,
+      usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+    let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("GPU Timer Read Buffer"),
+      size,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    Some(Self {
+      query_set,
+      resolve_buffer,
+      read_buffer,
+      period: queue.get_timestamp_period(),
+      passes: vec![],
+      recording: false,
+      pending_passes: vec![],
+      pending: None,
+      timings: vec![],
+    })
+  }
+
+  /// Writes the timestamp the first pass is measured from.
+  pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) {
+    self.passes.clear();
+    self.recording = self.pending.is_none();
+    if self.recording {
+      encoder.write_timestamp(&self.query_set, 0);
+    }
+  }
+
+  /// Ends the pass called `name`, it covers everything encoded since the previous mark.
+  pub fn mark(&mut self, encoder: &mut wgpu::CommandEncoder, name: &'static str) {
+    if !self.recording || self.passes.len() as u32 + 1 >= MAX_TIMESTAMPS {
+      return;
+    }
+    self.passes.push(name);
+    encoder.write_timestamp(&self.query_set, self.passes.len() as u32);
+  }
+
+  /// Copies the timestamps somewhere they can be read from, call before submitting.
+  pub fn finish(&mut self, encoder: &mut wgpu::CommandEncoder) {
+    if !self.recording {
+      return;
+    }
+    let count = self.passes.len() as u32 + 1;
+    encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+    encoder.copy_buffer_to_buffer(
+      &self.resolve_buffer,
+      0,
+      &self.read_buffer,
+      0,
+      (count as usize * std::mem::size_of::<u64>()) as wgpu::BufferAddress,
+    );
+  }
+
+  /// Starts reading back the submitted frame and picks up any earlier frame that is ready.
+  pub fn read_back(&mut self, device: &wgpu::Device) {
+    if self.recording {
+      self.recording = false;
+      let (sender, receiver) = std::sync::mpsc::channel();
+      self
+        .read_buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| {
+          sender.send(result).ok();
+        });
+      self.pending = Some(receiver);
+      std::mem::swap(&mut self.passes, &mut self.pending_passes);
+    }
+
+    let Some(receiver) = &self.pending else {
+      return;
+    };
+    device.poll(wgpu::Maintain::Poll);
+    match receiver.try_recv() {
+      Err(TryRecvError::Empty) => return,
+      Ok(Ok(())) => {
+        let timestamps = {
+          let data = self.read_buffer.slice(..).get_mapped_range();
+          data
+            .chunks_exact(std::mem::size_of::<u64>())
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<_>>()
+        };
+        self.read_buffer.unmap();
+
+        self.timings.clear();
+        for (i, &name) in self.pending_passes.iter().enumerate() {
+          let ticks = timestamps[i + 1].wrapping_sub(timestamps[i]);
+          self
+            .timings
+            .push((name, ticks as f32 * self.period / 1_000_000.0));
+        }
+      }
+      // the buffer is left unmapped, the next frame just tries again
+      Ok(Err(_)) | Err(TryRecvError::Disconnected) => {}
+    }
+    self.pending = None;
+  }
+
+  pub fn timings(&self) -> &[(&'static str, f32)] {
+    &self.timings
+  }
+}
@@ -1,4 +1,4 @@
-use wgpu::util::DeviceExt;
+use cgmath::{InnerSpace, Vector3};
 
 use crate::engine::render::{
   create_render_pipeline,
@@ -6,94 +6,253 @@ use crate::engine::render::{
   texture,
 };
 
+const INITIAL_CAPACITY: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub enum LightKind {
+  Point,
+  /// lights everything from `direction`, ignoring position and range
+  Directional,
+  /// cone of light around `direction`, angles in degrees
+  Spot {
+    inner_angle: f32,
+    outer_angle: f32,
+  },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+  pub kind: LightKind,
+  pub position: Vector3<f32>,
+  pub direction: Vector3<f32>,
+  pub color: [f32; 3],
+  pub intensity: f32,
+  /// distance at which the light fades out completely, 0 for no falloff
+  pub range: f32,
+}
+
+impl Light {
+  pub fn point(position: (f32, f32, f32), color: [f32; 3]) -> Self {
+    Self {
+      kind: LightKind::Point,
+      position: position.into(),
+      direction: -Vector3::unit_y(),
+      color,
+      intensity: 1.0,
+      range: 0.0,
+    }
+  }
+
+  pub fn directional(direction: (f32, f32, f32), color: [f32; 3]) -> Self {
+    Self {
+      kind: LightKind::Directional,
+      position: Vector3::new(0.0, 0.0, 0.0),
+      direction: direction.into(),
+      color,
+      intensity: 1.0,
+      range: 0.0,
+    }
+  }
+
+  pub fn spot(
+    position: (f32, f32, f32),
+    direction: (f32, f32, f32),
+    color: [f32; 3],
+    angle: f32,
+  ) -> Self {
+    Self {
+      kind: LightKind::Spot {
+        inner_angle: angle * 0.8,
+        outer_angle: angle,
+      },
+      position: position.into(),
+      direction: direction.into(),
+      color,
+      intensity: 1.0,
+      range: 0.0,
+    }
+  }
+
+  fn to_raw(self) -> LightRaw {
+    let (kind, cos_inner, cos_outer) = match self.kind {
+      LightKind::Point => (0, 0.0, 0.0),
+      LightKind::Directional => (1, 0.0, 0.0),
+      LightKind::Spot {
+        inner_angle,
+        outer_angle,
+      } => (
+        2,
+        inner_angle.to_radians().cos(),
+        outer_angle.to_radians().cos(),
+      ),
+    };
+    let direction = if self.direction.magnitude2() > 0.0 {
+      self.direction.normalize()
+    } else {
+      -Vector3::unit_y()
+    };
+
+    LightRaw {
+      position: self.position.into(),
+      kind,
+      direction: direction.into(),
+      range: self.range,
+      color: self.color,
+      intensity: self.intensity,
+      cos_inner,
+      cos_outer,
+      _padding: [0; 2],
+    }
+  }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct LightUniform {
-  pub position: [f32; 3],
-  _padding: u32,
-  pub color: [f32; 3],
-  _padding2: u32,
+struct LightRaw {
+  position: [f32; 3],
+  kind: u32,
+  direction: [f32; 3],
+  range: f32,
+  color: [f32; 3],
+  intensity: f32,
+  cos_inner: f32,
+  cos_outer: f32,
+  _padding: [u32; 2],
 }
 
-pub struct Light {
-  pub uniform: LightUniform,
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightHeader {
+  count: u32,
+  _padding: [u32; 3],
+}
+
+pub struct LightState {
+  pub count: u32,
   pub buffer: wgpu::Buffer,
   pub bind_group: wgpu::BindGroup,
   pub bind_group_layout: wgpu::BindGroupLayout,
   pub render_pipeline: wgpu::RenderPipeline,
+  pipeline_layout: wgpu::PipelineLayout,
+  capacity: usize,
 }
 
-impl Light {
+impl LightState {
   pub fn new(
     device: &wgpu::Device,
     camera_layout: &wgpu::BindGroupLayout,
-    shader_source: wgpu::ShaderSource,
-    tex_format: wgpu::TextureFormat,
-    position: [f32; 3],
-    color: [f32; 3],
+    sample_count: u32,
   ) -> Self {
-    let uniform = LightUniform {
-      position,
-      _padding: 0,
-      color,
-      _padding2: 0,
-    };
-
-    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some("Light VB"),
-      contents: bytemuck::cast_slice(&[uniform]),
-      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-    });
+    let buffer = create_buffer(device, INITIAL_CAPACITY);
 
     let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
       entries: &[wgpu::BindGroupLayoutEntry {
         binding: 0,
         visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
         ty: wgpu::BindingType::Buffer {
-          ty: wgpu::BufferBindingType::Uniform,
+          ty: wgpu::BufferBindingType::Storage { read_only: true },
           has_dynamic_offset: false,
           min_binding_size: None,
         },
         count: None,
       }],
-      label: None,
+      label: Some("light_bind_group_layout"),
     });
 
-    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-      layout: &bind_group_layout,
-      entries: &[wgpu::BindGroupEntry {
-        binding: 0,
-        resource: buffer.as_entire_binding(),
-      }],
-      label: None,
-    });
+    let bind_group = create_bind_group(device, &bind_group_layout, &buffer);
 
-    let render_pipeline = {
-      let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Light Pipeline Layout"),
-        bind_group_layouts: &[camera_layout, &bind_group_layout],
-        push_constant_ranges: &[],
-      });
-      let shader = wgpu::ShaderModuleDescriptor {
-        label: Some("Light Shader"),
-        source: shader_source,
-      };
-      create_render_pipeline(
-        &device,
-        &layout,
-        tex_format,
-        Some(texture::Texture::DEPTH_FORMAT),
-        &[mesh::MeshVertex::desc()],
-        shader,
-      )
-    };
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Light Pipeline Layout"),
+      bind_group_layouts: &[camera_layout, &bind_group_layout],
+      push_constant_ranges: &[],
+    });
+    let render_pipeline = create_pipeline(device, &pipeline_layout, sample_count);
 
     Self {
-      uniform,
+      count: 0,
       buffer,
       bind_group,
       bind_group_layout,
       render_pipeline,
+      pipeline_layout,
+      capacity: INITIAL_CAPACITY,
     }
   }
+
+  pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+    self.render_pipeline = create_pipeline(device, &self.pipeline_layout, sample_count);
+  }
+
+  /// Uploads `lights`, growing the storage buffer when there are more lights than it can hold.
+  pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lights: &[Light]) {
+    if lights.len() > self.capacity {
+      self.capacity = lights.len().next_power_of_two();
+      self.buffer = create_buffer(device, self.capacity);
+      self.bind_group = create_bind_group(device, &self.bind_group_layout, &self.buffer);
+    }
+
+    self.count = lights.len() as u32;
+    let header = LightHeader {
+      count: self.count,
+      _padding: [0; 3],
+    };
+    let raw = lights
+      .iter()
+      .map(|light| light.to_raw())
+      .collect::<Vec<_>>();
+
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[header]));
+    queue.write_buffer(
+      &self.buffer,
+      std::mem::size_of::<LightHeader>() as wgpu::BufferAddress,
+      bytemuck::cast_slice(&raw),
+    );
+  }
+}
+
+fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+  device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Light Buffer"),
+    size: (std::mem::size_of::<LightHeader>() + std::mem::size_of::<LightRaw>() * capacity)
+      as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
+}
+
+fn create_bind_group(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+  device.create_bind_group(&wgpu::BindGroupDescriptor {
+    layout,
+    entries: &[wgpu::BindGroupEntry {
+      binding: 0,
+      resource: buffer.as_entire_binding(),
+    }],
+    label: Some("light_bind_group"),
+  })
+}
+
+fn create_pipeline(
+  device: &wgpu::Device,
+  layout: &wgpu::PipelineLayout,
+  sample_count: u32,
+) -> wgpu::RenderPipeline {
+  let shader = wgpu::ShaderModuleDescriptor {
+    label: Some("Light Shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("shader/light.wgsl").into()),
+  };
+  create_render_pipeline(
+    device,
+    layout,
+    texture::Texture::HDR_FORMAT,
+    Some(texture::Texture::DEPTH_FORMAT),
+    &[mesh::MeshVertex::desc()],
+    shader,
+    Some(wgpu::Face::Back),
+    false,
+    sample_count,
+  )
 }
@@ -0,0 +1,131 @@
+/// Where the surface texture comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureMapping {
+  /// plain base color
+  None,
+  /// the mesh's own texture coordinates
+  Uv,
+  /// projected along the world axes, for stretched boxes without usable UVs
+  Triplanar,
+}
+
+/// How the surface of an object is shaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+  pub base_color: [f32; 3],
+  /// fraction of every light that reaches the surface even when facing away from it
+  pub ambient: f32,
+  pub specular_strength: f32,
+  /// specular exponent, higher gives smaller and sharper highlights
+  pub shininess: f32,
+  /// how strongly the object glows in its own color
+  pub emissive: f32,
+  /// skips lighting and shadows, the object is drawn in its base color
+  pub unlit: bool,
+  /// 1 is fully opaque
+  pub opacity: f32,
+  /// the grid texture is multiplied into the base color
+  pub texture: TextureMapping,
+  /// texture repeats per world unit for triplanar mapping, per UV unit otherwise
+  pub texture_scale: f32,
+}
+
+impl Material {
+  pub fn new(base_color: [f32; 3]) -> Self {
+    Self {
+      base_color,
+      ..Default::default()
+    }
+  }
+
+  /// Sets a property from a level file option, `name=value` or just `unlit`.
+  pub fn set_option(&mut self, option: &str) -> anyhow::Result<()> {
+    if option == "unlit" {
+      self.unlit = true;
+      return Ok(());
+    }
+
+    let Some((name, value)) = option.split_once('=') else {
+      anyhow::bail!(
+        "expected a material option like `emissive=1`, found `{}`",
+        option
+      );
+    };
+    if name == "texture" {
+      self.texture = match value {
+        "none" => TextureMapping::None,
+        "uv" => TextureMapping::Uv,
+        "triplanar" => TextureMapping::Triplanar,
+        _ => anyhow::bail!("unknown texture mapping `{}`", value),
+      };
+      return Ok(());
+    }
+    let value = value.parse::<f32>()?;
+    match name {
+      "ambient" => self.ambient = value,
+      "specular" => self.specular_strength = value,
+      "shininess" => self.shininess = value,
+      "emissive" => self.emissive = value,
+      "opacity" => self.opacity = value.clamp(0.0, 1.0),
+      "texture_scale" => self.texture_scale = value,
+      _ => anyhow::bail!("unknown material option `{}`", name),
+    }
+    Ok(())
+  }
+
+  /// Drawn in the blended pass after everything opaque, and casts no shadow.
+  pub fn is_transparent(&self) -> bool {
+    self.opacity < 1.0
+  }
+
+  pub(crate) fn to_raw(self) -> MaterialRaw {
+    MaterialRaw {
+      color: [
+        self.base_color[0],
+        self.base_color[1],
+        self.base_color[2],
+        self.opacity,
+      ],
+      shading: [
+        self.ambient,
+        self.specular_strength,
+        self.shininess,
+        self.emissive,
+      ],
+      params: [
+        if self.unlit { 1.0 } else { 0.0 },
+        self.texture as u32 as f32,
+        self.texture_scale,
+        0.0,
+      ],
+    }
+  }
+}
+
+impl Default for Material {
+  fn default() -> Self {
+    Self {
+      base_color: [1.0, 1.0, 1.0],
+      ambient: 0.1,
+      specular_strength: 1.0,
+      shininess: 32.0,
+      emissive: 0.0,
+      unlit: false,
+      opacity: 1.0,
+      texture: TextureMapping::None,
+      texture_scale: 1.0,
+    }
+  }
+}
+
+/// Material as it is laid out in the instance buffers, at shader locations 12 to 14.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct MaterialRaw {
+  /// base color and opacity
+  color: [f32; 4],
+  /// ambient, specular strength, shininess and emissive
+  shading: [f32; 4],
+  /// unlit, texture mapping and texture scale
+  params: [f32; 4],
+}
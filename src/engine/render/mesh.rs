@@ -1,7 +1,28 @@
-use std::ops::{Mul, Range};
+use std::{f32::consts::PI, ops::Range};
 
-use cgmath::Vector4;
-use wgpu::{util::DeviceExt, Device};
+use wgpu::util::DeviceExt;
+
+/// The regular meshes the renderer has loaded, `GameObject`s pick one of these. Only changes
+/// what is drawn, collision stays a box.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Shape3d {
+  /// `cube.obj`, corners at -1 and 1 on every axis
+  #[default]
+  Cube,
+  /// unit sphere with fewer faces the further away it is
+  Sphere,
+}
+
+impl Shape3d {
+  pub const ALL: [Shape3d; 2] = [Shape3d::Cube, Shape3d::Sphere];
+
+  pub(crate) fn index(self) -> usize {
+    match self {
+      Shape3d::Cube => 0,
+      Shape3d::Sphere => 1,
+    }
+  }
+}
 
 pub trait Vertex {
   fn desc() -> wgpu::VertexBufferLayout<'static>;
@@ -12,6 +33,7 @@ pub trait Vertex {
 pub struct MeshVertex {
   pub position: [f32; 3],
   pub normal: [f32; 3],
+  pub tex_coords: [f32; 2],
 }
 
 impl Vertex for MeshVertex {
@@ -31,88 +53,110 @@ impl Vertex for MeshVertex {
           shader_location: 1,
           format: wgpu::VertexFormat::Float32x3,
         },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+          shader_location: 2,
+          format: wgpu::VertexFormat::Float32x2,
+        },
       ],
     }
   }
 }
 
-pub struct Mesh4d {
+pub struct Mesh {
   pub name: String,
-  pub vertices: Vec<Vector4<f32>>,
-  pub normals: Vec<[f32; 3]>,
-  pub indices: Vec<u32>,
-  pub angle: f32,
+  pub vertex_buffer: wgpu::Buffer,
+  pub index_buffer: wgpu::Buffer,
+  pub num_elements: u32,
 }
 
-impl Mesh4d {
-  pub fn project(&self, device: &Device) -> Mesh {
-    let focal_length = 100.0;
-
-    let angle = self.angle * 3.1415/180.0;
-    #[rustfmt::skip]
-    let rotation1 = cgmath::Matrix4::new(
-      1.0, 0.0, 0.0, 0.0, 
-      0.0, f32::cos(angle*0.5), 0.0, f32::sin(angle*0.5), 
-      0.0, 0.0, 1.0, 0.0, 
-      0.0, -f32::sin(angle*0.5), 0.0, f32::cos(angle*0.5)
-    );
-    #[rustfmt::skip]
-    let rotation2 = cgmath::Matrix4::new(
-      f32::cos(angle), 0.0, 0.0, f32::sin(angle), 
-      0.0, 1.0, 0.0, 0.0, 
-      0.0, 0.0, 1.0, 0.0, 
-      -f32::sin(angle), 0.0, 0.0, f32::cos(angle)
-    );
-    #[rustfmt::skip]
-    let rotation3 = cgmath::Matrix4::new(
-      1.0, 0.0, 0.0, 0.0, 
-      0.0, 1.0, 0.0, 0.0,
-      0.0, 0.0, f32::cos(angle * 0.75), f32::sin(angle * 0.75), 
-      0.0, 0.0, -f32::sin(angle * 0.75), f32::cos(angle * 0.75), 
-    );
-
-    let vertices = self
-      .vertices
-      .iter()
-      .enumerate()
-      .map(|(i, v)| {
-        // let vertex = v;
-        let vertex = rotation1.mul(rotation2).mul(rotation3).mul(v);
-        MeshVertex {
-          position: [
-            (focal_length * vertex.x) / (focal_length + vertex.w),
-            (focal_length * vertex.y) / (focal_length + vertex.w),
-            (focal_length * vertex.z) / (focal_length + vertex.w),
-          ],
-          normal: self.normals[i],
-        }
-      })
-      .collect::<Vec<_>>();
-
+impl Mesh {
+  pub fn new(device: &wgpu::Device, name: &str, vertices: &[MeshVertex], indices: &[u32]) -> Self {
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some(&format!("{:?} Vertex Buffer", self.name)),
-      contents: bytemuck::cast_slice(&vertices),
+      label: Some(&format!("{:?} Vertex Buffer", name)),
+      contents: bytemuck::cast_slice(vertices),
       usage: wgpu::BufferUsages::VERTEX,
     });
     let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some(&format!("{:?} Index Buffer", self.name)),
-      contents: bytemuck::cast_slice(&self.indices),
+      label: Some(&format!("{:?} Index Buffer", name)),
+      contents: bytemuck::cast_slice(indices),
       usage: wgpu::BufferUsages::INDEX,
     });
-    Mesh {
-      name: self.name.to_string(),
+    Self {
+      name: name.to_string(),
       vertex_buffer,
       index_buffer,
-      num_elements: self.indices.clone().len() as u32,
+      num_elements: indices.len() as u32,
+    }
+  }
+
+  /// Unit sphere made of `segments` slices around y and `rings` from pole to pole.
+  pub fn sphere(device: &wgpu::Device, segments: u32, rings: u32) -> Self {
+    let mut vertices = Vec::with_capacity(((segments + 1) * (rings + 1)) as usize);
+    for ring in 0..=rings {
+      let v = ring as f32 / rings as f32;
+      let (sin_phi, cos_phi) = (v * PI).sin_cos();
+      // the seam repeats the first column so the texture coordinates can wrap
+      for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let (sin_theta, cos_theta) = (u * 2.0 * PI).sin_cos();
+        let position = [sin_phi * cos_theta, cos_phi, sin_phi * sin_theta];
+        vertices.push(MeshVertex {
+          position,
+          normal: position,
+          tex_coords: [u, v],
+        });
+      }
     }
+
+    let mut indices = Vec::with_capacity((segments * rings * 6) as usize);
+    for ring in 0..rings {
+      for segment in 0..segments {
+        let top = ring * (segments + 1) + segment;
+        let bottom = top + segments + 1;
+        indices.extend([top, top + 1, bottom, top + 1, bottom + 1, bottom]);
+      }
+    }
+
+    Self::new(
+      device,
+      &format!("sphere {}x{}", segments, rings),
+      &vertices,
+      &indices,
+    )
   }
 }
 
-pub struct Mesh {
-  pub name: String,
-  pub vertex_buffer: wgpu::Buffer,
-  pub index_buffer: wgpu::Buffer,
-  pub num_elements: u32,
+/// A mesh and simpler versions of it for objects further away.
+pub struct LodMesh {
+  /// from most to least detailed, with the distance up to which each one is used, measured in
+  /// bounding radii of the object so big objects keep their detail for longer
+  levels: Vec<(Mesh, f32)>,
+}
+
+impl LodMesh {
+  pub fn new(levels: Vec<(Mesh, f32)>) -> Self {
+    assert!(!levels.is_empty(), "a LodMesh needs at least one mesh");
+    Self { levels }
+  }
+
+  pub fn single(mesh: Mesh) -> Self {
+    Self::new(vec![(mesh, f32::INFINITY)])
+  }
+
+  /// Picks the level for an object with a bounding sphere of `radius` at `distance`.
+  pub fn select(&self, distance: f32, radius: f32) -> usize {
+    let relative = distance / radius.max(f32::EPSILON);
+    self
+      .levels
+      .iter()
+      .position(|(_, max_distance)| relative <= *max_distance)
+      .unwrap_or(self.levels.len() - 1)
+  }
+
+  pub fn get(&self, lod: usize) -> &Mesh {
+    &self.levels[lod].0
+  }
 }
 
 pub trait DrawModel<'a> {
@@ -145,12 +189,6 @@ where
 }
 
 pub trait DrawLight<'a> {
-  fn draw_light_mesh(
-    &mut self,
-    mesh: &'a Mesh,
-    camera_bind_group: &'a wgpu::BindGroup,
-    light_bind_group: &'a wgpu::BindGroup,
-  );
   fn draw_light_mesh_instanced(
     &mut self,
     mesh: &'a Mesh,
@@ -164,14 +202,6 @@ impl<'a, 'b> DrawLight<'b> for wgpu::RenderPass<'a>
 where
   'b: 'a,
 {
-  fn draw_light_mesh(
-    &mut self,
-    mesh: &'b Mesh,
-    camera_bind_group: &'b wgpu::BindGroup,
-    light_bind_group: &'b wgpu::BindGroup,
-  ) {
-    self.draw_light_mesh_instanced(mesh, 0..1, camera_bind_group, light_bind_group);
-  }
   fn draw_light_mesh_instanced(
     &mut self,
     mesh: &'b Mesh,
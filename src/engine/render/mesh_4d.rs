@@ -0,0 +1,486 @@
+use std::collections::HashSet;
+
+use cgmath::{Deg, Matrix4, Quaternion, Rotation3, Vector3, Vector4};
+use wgpu::util::DeviceExt;
+
+use super::{
+  material::{Material, MaterialRaw},
+  mesh::Vertex,
+};
+
+/// how far along w the 4D eye sits when objects don't set their own
+pub const DEFAULT_VIEW_DISTANCE: f32 = 4.0;
+
+/// The 4D meshes the renderer has loaded, objects pick one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape4d {
+  /// the unit hypercube, corners at -1 and 1 on every axis
+  Tesseract,
+  /// `skrungle.obj` extruded along w
+  Skrungle,
+}
+
+impl Shape4d {
+  pub const ALL: [Shape4d; 2] = [Shape4d::Tesseract, Shape4d::Skrungle];
+
+  pub(crate) fn index(self) -> usize {
+    match self {
+      Shape4d::Tesseract => 0,
+      Shape4d::Skrungle => 1,
+    }
+  }
+}
+
+/// How a 4D object is turned into something 3D.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View4d {
+  /// perspective projection of the whole object from a point along w
+  Projection,
+  /// the 3D cross section where the object meets the camera's w, so the object changes shape,
+  /// appears and vanishes as `Camera::w` moves through it
+  Slice,
+}
+
+impl View4d {
+  pub const ALL: [View4d; 2] = [View4d::Projection, View4d::Slice];
+}
+
+/// Rotation in the three planes that involve w, in degrees. Rotations in the xy, yz and zx
+/// planes are ordinary 3D rotations and live on `Object4d::rotation`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rotation4d {
+  pub xw: f32,
+  pub yw: f32,
+  pub zw: f32,
+}
+
+impl Rotation4d {
+  pub fn new(xw: f32, yw: f32, zw: f32) -> Self {
+    Self { xw, yw, zw }
+  }
+
+  pub fn matrix(&self) -> Matrix4<f32> {
+    plane_rotation(0, self.xw) * plane_rotation(1, self.yw) * plane_rotation(2, self.zw)
+  }
+}
+
+/// Rotates the `axis` and w axes into each other.
+fn plane_rotation(axis: usize, angle: f32) -> Matrix4<f32> {
+  let (sin, cos) = angle.to_radians().sin_cos();
+  let mut matrix = Matrix4::from_scale(1.0);
+  matrix[axis][axis] = cos;
+  matrix[axis][3] = -sin;
+  matrix[3][axis] = sin;
+  matrix[3][3] = cos;
+  matrix
+}
+
+/// A 4D object, rotated in 4D, projected down to 3D and then placed like a `GameObject`.
+pub struct Object4d {
+  pub shape: Shape4d,
+  pub view: View4d,
+  pub position: Vector3<f32>,
+  /// position along w, only matters when sliced
+  pub w: f32,
+  /// 3D rotation in degrees, applied after the projection
+  pub rotation: Vector3<f32>,
+  pub rotation_4d: Rotation4d,
+  pub scale: Vector3<f32>,
+  pub material: Material,
+  /// distance from the 4D eye to the w = 0 hyperplane, smaller values exaggerate the
+  /// perspective between the near and far w ends of the object
+  pub view_distance: f32,
+}
+
+impl Object4d {
+  pub fn new<V: Into<Vector3<f32>>>(
+    shape: Shape4d,
+    position: V,
+    scale: V,
+    color: [f32; 3],
+  ) -> Self {
+    Self {
+      shape,
+      view: View4d::Projection,
+      position: position.into(),
+      w: 0.0,
+      rotation: Vector3::new(0.0, 0.0, 0.0),
+      rotation_4d: Rotation4d::default(),
+      scale: scale.into(),
+      material: Material::new(color),
+      view_distance: DEFAULT_VIEW_DISTANCE,
+    }
+  }
+
+  /// Adds to the 4D rotation, in degrees.
+  pub fn rotate_4d(&mut self, xw: f32, yw: f32, zw: f32) {
+    self.rotation_4d.xw = (self.rotation_4d.xw + xw) % 360.0;
+    self.rotation_4d.yw = (self.rotation_4d.yw + yw) % 360.0;
+    self.rotation_4d.zw = (self.rotation_4d.zw + zw) % 360.0;
+  }
+}
+
+/// A 4D shape kept on the CPU until uploaded. `indices` are triangles, the 2D faces drawn when
+/// projecting, `cells` are tetrahedra covering its 3D boundary, which are cut when slicing.
+pub struct Mesh4d {
+  pub name: String,
+  pub vertices: Vec<Vector4<f32>>,
+  pub indices: Vec<u32>,
+  pub cells: Vec<[u32; 4]>,
+}
+
+impl Mesh4d {
+  pub fn tesseract() -> Self {
+    // corner i has bit k set when its k-th coordinate is positive
+    let vertices = (0..16u32)
+      .map(|i| {
+        let axis = |k: u32| if i & (1 << k) != 0 { 1.0 } else { -1.0 };
+        Vector4::new(axis(0), axis(1), axis(2), axis(3))
+      })
+      .collect();
+
+    // every square face spans two axes with the other two fixed
+    let mut indices = vec![];
+    for a in 0..4 {
+      for b in a + 1..4 {
+        let fixed = (0..16u32).filter(|i| i & (1 << a | 1 << b) == 0);
+        for base in fixed {
+          let corners = [base, base | 1 << a, base | 1 << a | 1 << b, base | 1 << b];
+          indices.extend([corners[0], corners[1], corners[2]]);
+          indices.extend([corners[0], corners[2], corners[3]]);
+        }
+      }
+    }
+
+    // the boundary is 8 cubes, one on each side of every axis, each split into 6 tetrahedra
+    // that walk from the low corner to the high one along the axes in every order
+    let mut cells = vec![];
+    for axis in 0..4 {
+      let others = (0..4).filter(|&k| k != axis).collect::<Vec<u32>>();
+      for side in [0, 1 << axis] {
+        for [a, b, c] in [
+          [0, 1, 2],
+          [0, 2, 1],
+          [1, 0, 2],
+          [1, 2, 0],
+          [2, 0, 1],
+          [2, 1, 0],
+        ] {
+          let first = side | 1 << others[a];
+          let second = first | 1 << others[b];
+          cells.push([side, first, second, second | 1 << others[c]]);
+        }
+      }
+    }
+
+    Self {
+      name: "tesseract".to_string(),
+      vertices,
+      indices,
+      cells,
+    }
+  }
+
+  /// Stretches a 3D triangle mesh along w into a prism from `-depth / 2` to `depth / 2`, made
+  /// of the mesh at both ends and a quad along w for every edge. Slicing anywhere between the
+  /// ends gives back the original mesh.
+  pub fn extrude(name: &str, positions: &[Vector3<f32>], indices: &[u32], depth: f32) -> Self {
+    let count = positions.len() as u32;
+    let vertices = [-depth * 0.5, depth * 0.5]
+      .into_iter()
+      .flat_map(|w| positions.iter().map(move |p| p.extend(w)))
+      .collect();
+
+    let mut extruded = indices.to_vec();
+    extruded.extend(indices.iter().map(|i| i + count));
+
+    let mut edges = HashSet::new();
+    for triangle in indices.chunks_exact(3) {
+      for (a, b) in [
+        (triangle[0], triangle[1]),
+        (triangle[1], triangle[2]),
+        (triangle[2], triangle[0]),
+      ] {
+        if edges.insert((a.min(b), a.max(b))) {
+          extruded.extend([a, b, b + count]);
+          extruded.extend([a, b + count, a + count]);
+        }
+      }
+    }
+
+    // the ends are solid 3D cells the hyperplane never cuts through, so only the sides are
+    // split up, each triangle times w is a prism of 3 tetrahedra. Sorting the corners first
+    // makes neighbouring prisms split their shared quad along the same diagonal
+    let cells = indices
+      .chunks_exact(3)
+      .flat_map(|triangle| {
+        let mut triangle = [triangle[0], triangle[1], triangle[2]];
+        triangle.sort_unstable();
+        let [a, b, c] = triangle;
+        let [a2, b2, c2] = [a + count, b + count, c + count];
+        [[a, b, c, c2], [a, b, b2, c2], [a, a2, b2, c2]]
+      })
+      .collect();
+
+    Self {
+      name: name.to_string(),
+      vertices,
+      indices: extruded,
+      cells,
+    }
+  }
+
+  /// Uploads the mesh once, the projection happens in the vertex shader every frame.
+  pub fn upload(&self, device: &wgpu::Device) -> GpuMesh4d {
+    // every corner carries the other two corners of its triangle so the shader can work out
+    // the face normal after projecting
+    let vertices = self
+      .indices
+      .chunks_exact(3)
+      .flat_map(|triangle| {
+        let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].into());
+        [
+          Vertex4d {
+            position: a,
+            next: b,
+            previous: c,
+          },
+          Vertex4d {
+            position: b,
+            next: c,
+            previous: a,
+          },
+          Vertex4d {
+            position: c,
+            next: a,
+            previous: b,
+          },
+        ]
+      })
+      .collect::<Vec<_>>();
+
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some(&format!("{:?} Vertex Buffer", self.name)),
+      contents: bytemuck::cast_slice(&vertices),
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    // a tetrahedron is cut into at most a quad, so every cell gets two triangles that the
+    // shader collapses when they aren't needed
+    let cell_vertices = self
+      .cells
+      .iter()
+      .flat_map(|cell| {
+        let corners = cell.map(|i| self.vertices[i as usize].into());
+        [CellVertex { corners }; 6]
+      })
+      .collect::<Vec<_>>();
+
+    let cell_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some(&format!("{:?} Cell Buffer", self.name)),
+      contents: bytemuck::cast_slice(&cell_vertices),
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    GpuMesh4d {
+      vertex_buffer,
+      num_vertices: vertices.len() as u32,
+      cell_buffer,
+      num_cell_vertices: cell_vertices.len() as u32,
+    }
+  }
+}
+
+pub struct GpuMesh4d {
+  pub vertex_buffer: wgpu::Buffer,
+  pub num_vertices: u32,
+  pub cell_buffer: wgpu::Buffer,
+  pub num_cell_vertices: u32,
+}
+
+impl GpuMesh4d {
+  /// The buffer and vertex count to draw for `view`.
+  pub fn vertices(&self, view: View4d) -> (&wgpu::Buffer, u32) {
+    match view {
+      View4d::Projection => (&self.vertex_buffer, self.num_vertices),
+      View4d::Slice => (&self.cell_buffer, self.num_cell_vertices),
+    }
+  }
+}
+
+/// One corner of the two triangles a tetrahedron is cut into, which corner comes from
+/// the vertex index.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CellVertex {
+  corners: [[f32; 4]; 4],
+}
+
+impl Vertex for CellVertex {
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    use std::mem;
+    wgpu::VertexBufferLayout {
+      array_stride: mem::size_of::<CellVertex>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &[
+        wgpu::VertexAttribute {
+          offset: 0,
+          shader_location: 0,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+          shader_location: 1,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+          shader_location: 2,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+          shader_location: 3,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+      ],
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex4d {
+  position: [f32; 4],
+  next: [f32; 4],
+  previous: [f32; 4],
+}
+
+impl Vertex for Vertex4d {
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    use std::mem;
+    wgpu::VertexBufferLayout {
+      array_stride: mem::size_of::<Vertex4d>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &[
+        wgpu::VertexAttribute {
+          offset: 0,
+          shader_location: 0,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+          shader_location: 1,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+          shader_location: 2,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+      ],
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance4dRaw {
+  model: [[f32; 4]; 4],
+  rotation: [[f32; 4]; 4],
+  material: MaterialRaw,
+  view_distance: f32,
+  slice_w: f32,
+}
+
+impl Instance4dRaw {
+  /// `camera_w` is where the camera sits along w, sliced objects are cut there.
+  pub fn from_object(object: &Object4d, camera_w: f32) -> Self {
+    let rotation = Quaternion::from_angle_x(Deg(object.rotation.x))
+      * Quaternion::from_angle_y(Deg(object.rotation.y))
+      * Quaternion::from_angle_z(Deg(object.rotation.z));
+    let model = Matrix4::from_translation(object.position)
+      * Matrix4::from(rotation)
+      * Matrix4::from_nonuniform_scale(object.scale.x, object.scale.y, object.scale.z);
+
+    Self {
+      model: model.into(),
+      rotation: object.rotation_4d.matrix().into(),
+      material: object.material.to_raw(),
+      view_distance: object.view_distance,
+      slice_w: camera_w - object.w,
+    }
+  }
+}
+
+impl Vertex for Instance4dRaw {
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    use std::mem;
+    wgpu::VertexBufferLayout {
+      array_stride: mem::size_of::<Instance4dRaw>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Instance,
+      attributes: &[
+        wgpu::VertexAttribute {
+          offset: 0,
+          shader_location: 4,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+          shader_location: 5,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+          shader_location: 6,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+          shader_location: 7,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+          shader_location: 8,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+          shader_location: 9,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+          shader_location: 10,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 28]>() as wgpu::BufferAddress,
+          shader_location: 11,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 32]>() as wgpu::BufferAddress,
+          shader_location: 12,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 36]>() as wgpu::BufferAddress,
+          shader_location: 13,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 40]>() as wgpu::BufferAddress,
+          shader_location: 14,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 44]>() as wgpu::BufferAddress,
+          shader_location: 15,
+          format: wgpu::VertexFormat::Float32x2,
+        },
+      ],
+    }
+  }
+}
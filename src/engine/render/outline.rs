@@ -0,0 +1,291 @@
+use std::ops::Range;
+
+use crate::engine::{
+  game_object::GameObject,
+  render::{
+    create_render_pipeline,
+    mesh::{self, LodMesh, Shape3d, Vertex},
+    texture, InstanceRaw,
+  },
+};
+
+const MAX_OUTLINES: usize = 32;
+
+/// Whether an object is drawn with an outline, see `OutlineStyle` for the colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Highlight {
+  #[default]
+  None,
+  /// under the cursor
+  Hovered,
+  Selected,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OutlineStyle {
+  pub selected_color: [f32; 3],
+  pub hovered_color: [f32; 3],
+  /// in world units, the same on every side however the object is scaled
+  pub width: f32,
+}
+
+impl Default for OutlineStyle {
+  fn default() -> Self {
+    Self {
+      selected_color: [1.0, 0.6, 0.1],
+      hovered_color: [1.0, 1.0, 1.0],
+      width: 0.08,
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct OutlineRaw {
+  model: [[f32; 4]; 4],
+  /// rgb is the color, a the width
+  color: [f32; 4],
+}
+
+impl mesh::Vertex for OutlineRaw {
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    use std::mem;
+    wgpu::VertexBufferLayout {
+      array_stride: mem::size_of::<OutlineRaw>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Instance,
+      attributes: &[
+        wgpu::VertexAttribute {
+          offset: 0,
+          shader_location: 5,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+          shader_location: 6,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+          shader_location: 7,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+          shader_location: 8,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+          shader_location: 9,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+      ],
+    }
+  }
+}
+
+/// Draws highlighted objects a second time, inflated and with only their back faces, so the
+/// part sticking out around the object becomes its outline.
+pub struct OutlineState {
+  /// instance ranges sharing a shape, the transparent ones first
+  draws: Vec<OutlineDraw>,
+  pub render_pipeline: wgpu::RenderPipeline,
+  /// writes the depth of the back faces of transparent objects, otherwise the inside of the
+  /// outline would be visible through them
+  pub mask_pipeline: wgpu::RenderPipeline,
+  instance_buffer: wgpu::Buffer,
+  pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl OutlineState {
+  pub fn new(
+    device: &wgpu::Device,
+    camera_layout: &wgpu::BindGroupLayout,
+    sample_count: u32,
+  ) -> Self {
+    let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Outline Instance Buffer"),
+      size: (std::mem::size_of::<OutlineRaw>() * MAX_OUTLINES) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Outline Pipeline Layout"),
+      bind_group_layouts: &[camera_layout],
+      push_constant_ranges: &[],
+    });
+    let render_pipeline = create_pipeline(device, &pipeline_layout, sample_count);
+    let mask_pipeline = create_mask_pipeline(device, &pipeline_layout, sample_count);
+
+    Self {
+      draws: vec![],
+      render_pipeline,
+      mask_pipeline,
+      instance_buffer,
+      pipeline_layout,
+    }
+  }
+
+  pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+    self.render_pipeline = create_pipeline(device, &self.pipeline_layout, sample_count);
+    self.mask_pipeline = create_mask_pipeline(device, &self.pipeline_layout, sample_count);
+  }
+
+  /// Uploads the outlines of the highlighted `objects`, unhighlighted ones are skipped.
+  pub fn update(&mut self, queue: &wgpu::Queue, objects: &[&GameObject], style: &OutlineStyle) {
+    let mut outlines = objects
+      .iter()
+      .filter_map(|object| {
+        let color = match object.highlight {
+          Highlight::None => return None,
+          Highlight::Hovered => style.hovered_color,
+          Highlight::Selected => style.selected_color,
+        };
+        let raw = OutlineRaw {
+          model: InstanceRaw::from_game_object(object).model,
+          color: [color[0], color[1], color[2], style.width],
+        };
+        Some((object.material.is_transparent(), object.shape, raw))
+      })
+      .take(MAX_OUTLINES)
+      .collect::<Vec<_>>();
+    outlines.sort_by_key(|(transparent, shape, _)| (!transparent, *shape));
+
+    self.draws.clear();
+    for (index, &(transparent, shape, _)) in outlines.iter().enumerate() {
+      let index = index as u32;
+      match self.draws.last_mut() {
+        Some(draw) if draw.masked == transparent && draw.shape == shape => {
+          draw.instances.end = index + 1
+        }
+        _ => self.draws.push(OutlineDraw {
+          shape,
+          masked: transparent,
+          instances: index..index + 1,
+        }),
+      }
+    }
+
+    let raw = outlines
+      .into_iter()
+      .map(|(_, _, raw)| raw)
+      .collect::<Vec<_>>();
+    queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&raw));
+  }
+
+  /// Draws the outlines with the most detailed version of each object's mesh.
+  pub fn draw<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    meshes: &'a [LodMesh],
+    camera_bind_group: &'a wgpu::BindGroup,
+  ) {
+    if self.draws.is_empty() {
+      return;
+    }
+    render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+    render_pass.set_bind_group(0, camera_bind_group, &[]);
+
+    render_pass.set_pipeline(&self.mask_pipeline);
+    draw_meshes(
+      render_pass,
+      meshes,
+      self.draws.iter().filter(|draw| draw.masked),
+    );
+    render_pass.set_pipeline(&self.render_pipeline);
+    draw_meshes(render_pass, meshes, self.draws.iter());
+  }
+}
+
+struct OutlineDraw {
+  shape: Shape3d,
+  /// transparent objects, whose insides have to be masked first
+  masked: bool,
+  instances: Range<u32>,
+}
+
+fn draw_meshes<'a>(
+  render_pass: &mut wgpu::RenderPass<'a>,
+  meshes: &'a [LodMesh],
+  draws: impl Iterator<Item = &'a OutlineDraw>,
+) {
+  for draw in draws {
+    let mesh = meshes[draw.shape.index()].get(0);
+    render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+    render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    render_pass.draw_indexed(0..mesh.num_elements, 0, draw.instances.clone());
+  }
+}
+
+fn create_pipeline(
+  device: &wgpu::Device,
+  layout: &wgpu::PipelineLayout,
+  sample_count: u32,
+) -> wgpu::RenderPipeline {
+  let shader = wgpu::ShaderModuleDescriptor {
+    label: Some("Outline Shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("shader/outline.wgsl").into()),
+  };
+  create_render_pipeline(
+    device,
+    layout,
+    texture::Texture::HDR_FORMAT,
+    Some(texture::Texture::DEPTH_FORMAT),
+    &[mesh::MeshVertex::desc(), OutlineRaw::desc()],
+    shader,
+    Some(wgpu::Face::Front),
+    false,
+    sample_count,
+  )
+}
+
+/// Depth only version of the outline pipeline that leaves the mesh at its original size.
+fn create_mask_pipeline(
+  device: &wgpu::Device,
+  layout: &wgpu::PipelineLayout,
+  sample_count: u32,
+) -> wgpu::RenderPipeline {
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("Outline Shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("shader/outline.wgsl").into()),
+  });
+
+  device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some("Outline Mask Pipeline"),
+    layout: Some(layout),
+    vertex: wgpu::VertexState {
+      module: &shader,
+      entry_point: "vs_mask",
+      buffers: &[mesh::MeshVertex::desc(), OutlineRaw::desc()],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: &shader,
+      entry_point: "fs_main",
+      targets: &[Some(wgpu::ColorTargetState {
+        format: texture::Texture::HDR_FORMAT,
+        blend: None,
+        write_mask: wgpu::ColorWrites::empty(),
+      })],
+    }),
+    primitive: wgpu::PrimitiveState {
+      topology: wgpu::PrimitiveTopology::TriangleList,
+      front_face: wgpu::FrontFace::Ccw,
+      cull_mode: Some(wgpu::Face::Front),
+      ..Default::default()
+    },
+    depth_stencil: Some(wgpu::DepthStencilState {
+      format: texture::Texture::DEPTH_FORMAT,
+      depth_write_enabled: true,
+      depth_compare: wgpu::CompareFunction::Less,
+      stencil: wgpu::StencilState::default(),
+      bias: wgpu::DepthBiasState::default(),
+    }),
+    multisample: wgpu::MultisampleState {
+      count: sample_count,
+      mask: !0,
+      alpha_to_coverage_enabled: false,
+    },
+    multiview: None,
+  })
+}
@@ -0,0 +1,319 @@
+use crate::engine::GameState;
+
+use super::texture;
+
+pub use bloom::BloomState;
+pub use blur::BlurEffect;
+pub use hue_shift::HueShiftEffect;
+pub use speed_lines::SpeedLinesEffect;
+pub use vignette::VignetteEffect;
+
+mod bloom;
+mod blur;
+mod hue_shift;
+mod speed_lines;
+mod vignette;
+
+/// Effect strengths driven by gameplay, read by the post-process stack every frame.
+#[derive(Default)]
+pub struct ScreenEffects {
+  /// blur radius in pixels, used behind the pause menu
+  pub blur: f32,
+  /// 0..1, darkens the edges of the screen
+  pub vignette: f32,
+  /// 0..1, streaks rushing out from the center of the screen
+  pub speed_lines: f32,
+}
+
+/// The offscreen targets a post-process pass reads from.
+pub struct PassInput<'a> {
+  pub color: &'a wgpu::TextureView,
+  pub depth: &'a wgpu::TextureView,
+}
+
+/// A full-screen pass in the post-process stack. Every effect reads the HDR output of the
+/// previous one and writes an HDR image of the same size.
+pub trait PostEffect {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState);
+  fn enabled(&self) -> bool;
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  );
+  fn resize(&mut self, _device: &wgpu::Device, _width: u32, _height: u32) {}
+}
+
+/// Ordered list of effects applied to the rendered scene before it is presented.
+pub struct PostProcess {
+  effects: Vec<Box<dyn PostEffect>>,
+  targets: [texture::Texture; 2],
+  present: FullscreenPass,
+}
+
+impl PostProcess {
+  pub fn new(
+    device: &wgpu::Device,
+    output_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+  ) -> Self {
+    let mut post_process = Self {
+      effects: vec![],
+      targets: create_targets(device, width, height),
+      present: FullscreenPass::new(
+        device,
+        "Present",
+        include_str!("shader/present.wgsl"),
+        16,
+        output_format,
+      ),
+    };
+
+    post_process.push(Box::new(BloomState::new(device, width, height)));
+    post_process.push(Box::new(HueShiftEffect::new(device)));
+    post_process.push(Box::new(SpeedLinesEffect::new(device, width, height)));
+    post_process.push(Box::new(VignetteEffect::new(device)));
+    post_process.push(Box::new(BlurEffect::new(device, width, height)));
+
+    post_process
+  }
+
+  /// Adds an effect to the end of the stack.
+  pub fn push(&mut self, effect: Box<dyn PostEffect>) {
+    self.effects.push(effect);
+  }
+
+  pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    self.targets = create_targets(device, width, height);
+    for effect in &mut self.effects {
+      effect.resize(device, width, height);
+    }
+  }
+
+  pub fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    for effect in &mut self.effects {
+      effect.update(queue, game);
+    }
+  }
+
+  /// Runs every enabled effect over `scene`, ping-ponging between two targets, then writes
+  /// the result to `output`.
+  pub fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    scene: &texture::Texture,
+    depth: &texture::Texture,
+    output: &wgpu::TextureView,
+  ) {
+    let mut color = &scene.view;
+    let mut target = 0;
+    for effect in self.effects.iter().filter(|effect| effect.enabled()) {
+      let input = PassInput {
+        color,
+        depth: &depth.view,
+      };
+      effect.render(device, encoder, &input, &self.targets[target].view);
+      color = &self.targets[target].view;
+      target = 1 - target;
+    }
+
+    let input = PassInput {
+      color,
+      depth: &depth.view,
+    };
+    self.present.render(device, encoder, &input, output);
+  }
+}
+
+/// Single full-screen draw with its own shader and uniform buffer. The shader only needs an
+/// `fs_main` and a `params` uniform at binding 2, the bindings and vertex stage from
+/// `fullscreen.wgsl` are prepended to it.
+pub struct FullscreenPass {
+  label: &'static str,
+  buffer: wgpu::Buffer,
+  sampler: wgpu::Sampler,
+  layout: wgpu::BindGroupLayout,
+  pipeline: wgpu::RenderPipeline,
+}
+
+impl FullscreenPass {
+  pub fn new(
+    device: &wgpu::Device,
+    label: &'static str,
+    source: &str,
+    uniform_size: u64,
+    format: wgpu::TextureFormat,
+  ) -> Self {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some(label),
+      size: uniform_size,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      address_mode_u: wgpu::AddressMode::ClampToEdge,
+      address_mode_v: wgpu::AddressMode::ClampToEdge,
+      address_mode_w: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 2,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 3,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+          },
+          count: None,
+        },
+      ],
+      label: Some("post_process_bind_group_layout"),
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some(label),
+      source: wgpu::ShaderSource::Wgsl(
+        format!("{}{}", include_str!("shader/fullscreen.wgsl"), source).into(),
+      ),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Post Process Pipeline Layout"),
+      bind_group_layouts: &[&layout],
+      push_constant_ranges: &[],
+    });
+
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+      label: Some(label),
+      layout: Some(&pipeline_layout),
+      vertex: wgpu::VertexState {
+        module: &shader,
+        entry_point: "vs_main",
+        buffers: &[],
+      },
+      fragment: Some(wgpu::FragmentState {
+        module: &shader,
+        entry_point: "fs_main",
+        targets: &[Some(wgpu::ColorTargetState {
+          format,
+          blend: None,
+          write_mask: wgpu::ColorWrites::ALL,
+        })],
+      }),
+      primitive: wgpu::PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: wgpu::MultisampleState::default(),
+      multiview: None,
+    });
+
+    Self {
+      label,
+      buffer,
+      sampler,
+      layout,
+      pipeline,
+    }
+  }
+
+  pub fn write_uniform<T: bytemuck::Pod>(&self, queue: &wgpu::Queue, uniform: &T) {
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[*uniform]));
+  }
+
+  pub fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &self.layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(input.color),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(&self.sampler),
+        },
+        wgpu::BindGroupEntry {
+          binding: 2,
+          resource: self.buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 3,
+          resource: wgpu::BindingResource::TextureView(input.depth),
+        },
+      ],
+      label: Some("post_process_bind_group"),
+    });
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some(self.label),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: output,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+fn create_targets(device: &wgpu::Device, width: u32, height: u32) -> [texture::Texture; 2] {
+  [0, 1].map(|_| {
+    texture::Texture::create_render_target(
+      device,
+      width,
+      height,
+      texture::Texture::HDR_FORMAT,
+      "post_process_texture",
+    )
+  })
+}
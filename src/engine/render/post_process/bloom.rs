@@ -0,0 +1,381 @@
+use crate::engine::{render::texture, GameState};
+
+use super::{PassInput, PostEffect};
+
+const BLOOM_LEVELS: usize = 5;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomUniform {
+  threshold: f32,
+  knee: f32,
+  intensity: f32,
+  _padding: f32,
+}
+
+/// Threshold, downsample/upsample blur and composite of the bright parts back onto the scene.
+pub struct BloomState {
+  enabled: bool,
+  buffer: wgpu::Buffer,
+  sampler: wgpu::Sampler,
+  layout: wgpu::BindGroupLayout,
+  composite_layout: wgpu::BindGroupLayout,
+  threshold_pipeline: wgpu::RenderPipeline,
+  downsample_pipeline: wgpu::RenderPipeline,
+  upsample_pipeline: wgpu::RenderPipeline,
+  composite_pipeline: wgpu::RenderPipeline,
+  levels: Vec<texture::Texture>,
+  level_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl BloomState {
+  pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Bloom Buffer"),
+      size: std::mem::size_of::<BloomUniform>() as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      address_mode_u: wgpu::AddressMode::ClampToEdge,
+      address_mode_v: wgpu::AddressMode::ClampToEdge,
+      address_mode_w: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility: wgpu::ShaderStages::FRAGMENT,
+      ty: wgpu::BindingType::Texture {
+        multisampled: false,
+        view_dimension: wgpu::TextureViewDimension::D2,
+        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      },
+      count: None,
+    };
+    let entries = [
+      texture_entry(0),
+      wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      texture_entry(3),
+    ];
+
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &entries[..3],
+      label: Some("bloom_bind_group_layout"),
+    });
+    let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &entries,
+      label: Some("bloom_composite_bind_group_layout"),
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("Bloom Shader"),
+      source: wgpu::ShaderSource::Wgsl(include_str!("../shader/bloom.wgsl").into()),
+    });
+
+    let threshold_pipeline = create_pipeline(
+      device,
+      &layout,
+      &shader,
+      "fs_threshold",
+      texture::Texture::HDR_FORMAT,
+      None,
+    );
+    let downsample_pipeline = create_pipeline(
+      device,
+      &layout,
+      &shader,
+      "fs_downsample",
+      texture::Texture::HDR_FORMAT,
+      None,
+    );
+    let upsample_pipeline = create_pipeline(
+      device,
+      &layout,
+      &shader,
+      "fs_upsample",
+      texture::Texture::HDR_FORMAT,
+      Some(wgpu::BlendState {
+        color: wgpu::BlendComponent {
+          src_factor: wgpu::BlendFactor::One,
+          dst_factor: wgpu::BlendFactor::One,
+          operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::REPLACE,
+      }),
+    );
+    let composite_pipeline = create_pipeline(
+      device,
+      &composite_layout,
+      &shader,
+      "fs_composite",
+      texture::Texture::HDR_FORMAT,
+      None,
+    );
+
+    let (levels, level_bind_groups) =
+      create_levels(device, &layout, &buffer, &sampler, width, height);
+
+    Self {
+      enabled: true,
+      buffer,
+      sampler,
+      layout,
+      composite_layout,
+      threshold_pipeline,
+      downsample_pipeline,
+      upsample_pipeline,
+      composite_pipeline,
+      levels,
+      level_bind_groups,
+    }
+  }
+}
+
+impl PostEffect for BloomState {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    let settings = &game.settings.video;
+    self.enabled = settings.bloom;
+    let uniform = BloomUniform {
+      threshold: settings.bloom_threshold,
+      knee: settings.bloom_threshold * 0.5,
+      intensity: settings.bloom_intensity,
+      _padding: 0.0,
+    };
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  /// Blurs the bright parts of the input and adds the result on top of it.
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    let threshold_bind_group = create_bind_group(
+      device,
+      &self.layout,
+      &self.buffer,
+      &self.sampler,
+      input.color,
+    );
+    fullscreen_pass(
+      encoder,
+      "Bloom Threshold Pass",
+      &self.threshold_pipeline,
+      &threshold_bind_group,
+      &self.levels[0].view,
+      true,
+    );
+    for i in 1..self.levels.len() {
+      fullscreen_pass(
+        encoder,
+        "Bloom Downsample Pass",
+        &self.downsample_pipeline,
+        &self.level_bind_groups[i - 1],
+        &self.levels[i].view,
+        true,
+      );
+    }
+    for i in (1..self.levels.len()).rev() {
+      fullscreen_pass(
+        encoder,
+        "Bloom Upsample Pass",
+        &self.upsample_pipeline,
+        &self.level_bind_groups[i],
+        &self.levels[i - 1].view,
+        false,
+      );
+    }
+
+    let composite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &self.composite_layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(input.color),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(&self.sampler),
+        },
+        wgpu::BindGroupEntry {
+          binding: 2,
+          resource: self.buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 3,
+          resource: wgpu::BindingResource::TextureView(&self.levels[0].view),
+        },
+      ],
+      label: Some("bloom_composite_bind_group"),
+    });
+    fullscreen_pass(
+      encoder,
+      "Bloom Composite Pass",
+      &self.composite_pipeline,
+      &composite_bind_group,
+      output,
+      true,
+    );
+  }
+
+  fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    (self.levels, self.level_bind_groups) = create_levels(
+      device,
+      &self.layout,
+      &self.buffer,
+      &self.sampler,
+      width,
+      height,
+    );
+  }
+}
+
+fn fullscreen_pass(
+  encoder: &mut wgpu::CommandEncoder,
+  label: &str,
+  pipeline: &wgpu::RenderPipeline,
+  bind_group: &wgpu::BindGroup,
+  target: &wgpu::TextureView,
+  clear: bool,
+) {
+  let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+    label: Some(label),
+    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+      view: target,
+      resolve_target: None,
+      ops: wgpu::Operations {
+        load: if clear {
+          wgpu::LoadOp::Clear(wgpu::Color::BLACK)
+        } else {
+          wgpu::LoadOp::Load
+        },
+        store: wgpu::StoreOp::Store,
+      },
+    })],
+    depth_stencil_attachment: None,
+    occlusion_query_set: None,
+    timestamp_writes: None,
+  });
+  render_pass.set_pipeline(pipeline);
+  render_pass.set_bind_group(0, bind_group, &[]);
+  render_pass.draw(0..3, 0..1);
+}
+
+fn create_pipeline(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  shader: &wgpu::ShaderModule,
+  entry_point: &str,
+  format: wgpu::TextureFormat,
+  blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+  let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+    label: Some("Bloom Pipeline Layout"),
+    bind_group_layouts: &[layout],
+    push_constant_ranges: &[],
+  });
+
+  device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some("Bloom Pipeline"),
+    layout: Some(&layout),
+    vertex: wgpu::VertexState {
+      module: shader,
+      entry_point: "vs_main",
+      buffers: &[],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: shader,
+      entry_point,
+      targets: &[Some(wgpu::ColorTargetState {
+        format,
+        blend,
+        write_mask: wgpu::ColorWrites::ALL,
+      })],
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+  })
+}
+
+fn create_bind_group(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  buffer: &wgpu::Buffer,
+  sampler: &wgpu::Sampler,
+  source: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+  device.create_bind_group(&wgpu::BindGroupDescriptor {
+    layout,
+    entries: &[
+      wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(source),
+      },
+      wgpu::BindGroupEntry {
+        binding: 1,
+        resource: wgpu::BindingResource::Sampler(sampler),
+      },
+      wgpu::BindGroupEntry {
+        binding: 2,
+        resource: buffer.as_entire_binding(),
+      },
+    ],
+    label: Some("bloom_bind_group"),
+  })
+}
+
+fn create_levels(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  buffer: &wgpu::Buffer,
+  sampler: &wgpu::Sampler,
+  width: u32,
+  height: u32,
+) -> (Vec<texture::Texture>, Vec<wgpu::BindGroup>) {
+  let levels = (1..=BLOOM_LEVELS)
+    .map(|i| {
+      texture::Texture::create_render_target(
+        device,
+        (width >> i).max(1),
+        (height >> i).max(1),
+        texture::Texture::HDR_FORMAT,
+        "bloom_texture",
+      )
+    })
+    .collect::<Vec<_>>();
+
+  let level_bind_groups = levels
+    .iter()
+    .map(|level| create_bind_group(device, layout, buffer, sampler, &level.view))
+    .collect();
+
+  (levels, level_bind_groups)
+}
@@ -0,0 +1,98 @@
+use crate::engine::{render::texture, GameState};
+
+use super::{FullscreenPass, PassInput, PostEffect};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BlurUniform {
+  direction: [f32; 2],
+  radius: f32,
+  _padding: f32,
+}
+
+/// Separable gaussian blur of the whole screen, strength comes from `ScreenEffects::blur`.
+pub struct BlurEffect {
+  horizontal: FullscreenPass,
+  vertical: FullscreenPass,
+  intermediate: texture::Texture,
+  enabled: bool,
+}
+
+impl BlurEffect {
+  pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    let pass = |label| {
+      FullscreenPass::new(
+        device,
+        label,
+        include_str!("../shader/blur.wgsl"),
+        std::mem::size_of::<BlurUniform>() as u64,
+        texture::Texture::HDR_FORMAT,
+      )
+    };
+
+    Self {
+      horizontal: pass("Horizontal Blur Pass"),
+      vertical: pass("Vertical Blur Pass"),
+      intermediate: create_intermediate(device, width, height),
+      enabled: false,
+    }
+  }
+}
+
+impl PostEffect for BlurEffect {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    let radius = game.screen_effects.blur.max(0.0);
+    self.enabled = radius > 0.0;
+    self.horizontal.write_uniform(
+      queue,
+      &BlurUniform {
+        direction: [1.0, 0.0],
+        radius,
+        _padding: 0.0,
+      },
+    );
+    self.vertical.write_uniform(
+      queue,
+      &BlurUniform {
+        direction: [0.0, 1.0],
+        radius,
+        _padding: 0.0,
+      },
+    );
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    self
+      .horizontal
+      .render(device, encoder, input, &self.intermediate.view);
+    let input = PassInput {
+      color: &self.intermediate.view,
+      depth: input.depth,
+    };
+    self.vertical.render(device, encoder, &input, output);
+  }
+
+  fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    self.intermediate = create_intermediate(device, width, height);
+  }
+}
+
+fn create_intermediate(device: &wgpu::Device, width: u32, height: u32) -> texture::Texture {
+  texture::Texture::create_render_target(
+    device,
+    width,
+    height,
+    texture::Texture::HDR_FORMAT,
+    "blur_texture",
+  )
+}
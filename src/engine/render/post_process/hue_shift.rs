@@ -0,0 +1,59 @@
+use crate::engine::{render::texture, GameState};
+
+use super::{FullscreenPass, PassInput, PostEffect};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct HueShiftUniform {
+  angle: f32,
+  _padding: [f32; 3],
+}
+
+/// Rotates every color around the grey axis, set from the accessibility settings.
+pub struct HueShiftEffect {
+  pass: FullscreenPass,
+  enabled: bool,
+}
+
+impl HueShiftEffect {
+  pub fn new(device: &wgpu::Device) -> Self {
+    Self {
+      pass: FullscreenPass::new(
+        device,
+        "Hue Shift Pass",
+        include_str!("../shader/hue_shift.wgsl"),
+        std::mem::size_of::<HueShiftUniform>() as u64,
+        texture::Texture::HDR_FORMAT,
+      ),
+      enabled: false,
+    }
+  }
+}
+
+impl PostEffect for HueShiftEffect {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    let hue_shift = game.settings.accessibility.hue_shift;
+    self.enabled = hue_shift != 0.0;
+    self.pass.write_uniform(
+      queue,
+      &HueShiftUniform {
+        angle: hue_shift.to_radians(),
+        _padding: [0.0; 3],
+      },
+    );
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    self.pass.render(device, encoder, input, output);
+  }
+}
@@ -0,0 +1,71 @@
+use crate::engine::{render::texture, GameState};
+
+use super::{FullscreenPass, PassInput, PostEffect};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpeedLinesUniform {
+  intensity: f32,
+  time: f32,
+  aspect: f32,
+  _padding: f32,
+}
+
+/// Streaks around the edge of the screen, strength comes from `ScreenEffects::speed_lines`.
+pub struct SpeedLinesEffect {
+  pass: FullscreenPass,
+  start_time: instant::Instant,
+  aspect: f32,
+  enabled: bool,
+}
+
+impl SpeedLinesEffect {
+  pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    Self {
+      pass: FullscreenPass::new(
+        device,
+        "Speed Lines Pass",
+        include_str!("../shader/speed_lines.wgsl"),
+        std::mem::size_of::<SpeedLinesUniform>() as u64,
+        texture::Texture::HDR_FORMAT,
+      ),
+      start_time: instant::Instant::now(),
+      aspect: width as f32 / height as f32,
+      enabled: false,
+    }
+  }
+}
+
+impl PostEffect for SpeedLinesEffect {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    let intensity = game.screen_effects.speed_lines.clamp(0.0, 1.0);
+    self.enabled = game.settings.video.speed_lines && intensity > 0.0;
+    self.pass.write_uniform(
+      queue,
+      &SpeedLinesUniform {
+        intensity,
+        time: self.start_time.elapsed().as_secs_f32(),
+        aspect: self.aspect,
+        _padding: 0.0,
+      },
+    );
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    self.pass.render(device, encoder, input, output);
+  }
+
+  fn resize(&mut self, _device: &wgpu::Device, width: u32, height: u32) {
+    self.aspect = width as f32 / height as f32;
+  }
+}
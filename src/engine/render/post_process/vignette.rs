@@ -0,0 +1,59 @@
+use crate::engine::{render::texture, GameState};
+
+use super::{FullscreenPass, PassInput, PostEffect};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct VignetteUniform {
+  intensity: f32,
+  _padding: [f32; 3],
+}
+
+/// Darkens the edges of the screen, strength comes from `ScreenEffects::vignette`.
+pub struct VignetteEffect {
+  pass: FullscreenPass,
+  enabled: bool,
+}
+
+impl VignetteEffect {
+  pub fn new(device: &wgpu::Device) -> Self {
+    Self {
+      pass: FullscreenPass::new(
+        device,
+        "Vignette Pass",
+        include_str!("../shader/vignette.wgsl"),
+        std::mem::size_of::<VignetteUniform>() as u64,
+        texture::Texture::HDR_FORMAT,
+      ),
+      enabled: false,
+    }
+  }
+}
+
+impl PostEffect for VignetteEffect {
+  fn update(&mut self, queue: &wgpu::Queue, game: &GameState) {
+    let intensity = game.screen_effects.vignette.clamp(0.0, 1.0);
+    self.enabled = intensity > 0.0;
+    self.pass.write_uniform(
+      queue,
+      &VignetteUniform {
+        intensity,
+        _padding: [0.0; 3],
+      },
+    );
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn render(
+    &self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    input: &PassInput,
+    output: &wgpu::TextureView,
+  ) {
+    self.pass.render(device, encoder, input, output);
+  }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use crate::engine::{
+  profiler::{CpuSection, Profiler, HISTORY_FRAMES},
+  render::{color::Color, mesh, render_queue::FrameStats, ui::TextObject},
+};
+
+/// frame time at the top of the graph, in milliseconds
+const GRAPH_MAX: f32 = 50.0;
+/// frames up to this long are drawn green, up to `SLOW_FRAME_TIME` yellow and red after that
+const TARGET_FRAME_TIME: f32 = 1000.0 / 60.0;
+const SLOW_FRAME_TIME: f32 = 1000.0 / 30.0;
+/// in pixels, the graph sits in the top right corner with the text below it
+const GRAPH_WIDTH: f32 = 240.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+const MARGIN: f32 = 10.0;
+const TEXT_SIZE: f32 = 10.0;
+/// background, target line and a bar per frame
+const MAX_QUADS: usize = HISTORY_FRAMES + 2;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct QuadRaw {
+  /// min x, min y, max x and max y in normalized device coordinates
+  rect: [f32; 4],
+  color: [f32; 4],
+}
+
+impl mesh::Vertex for QuadRaw {
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    use std::mem;
+    wgpu::VertexBufferLayout {
+      array_stride: mem::size_of::<QuadRaw>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Instance,
+      attributes: &[
+        wgpu::VertexAttribute {
+          offset: 0,
+          shader_location: 0,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+          shader_location: 1,
+          format: wgpu::VertexFormat::Float32x4,
+        },
+      ],
+    }
+  }
+}
+
+/// Frame time graph and timings from the `Profiler`, drawn over the finished frame.
+pub struct ProfilerOverlay {
+  render_pipeline: wgpu::RenderPipeline,
+  instance_buffer: wgpu::Buffer,
+  count: u32,
+  /// queued with the rest of the UI text
+  pub text: TextObject,
+}
+
+impl ProfilerOverlay {
+  pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Profiler Overlay Buffer"),
+      size: (std::mem::size_of::<QuadRaw>() * MAX_QUADS) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let text = TextObject {
+      enabled: false,
+      size: TEXT_SIZE,
+      color: Color::from_rgb(1.0, 1.0, 1.0),
+      text: String::new(),
+      ..Default::default()
+    };
+
+    Self {
+      render_pipeline: create_pipeline(device, format),
+      instance_buffer,
+      count: 0,
+      text,
+    }
+  }
+
+  /// Lays out the overlay for a `width` by `height` frame, hidden unless
+  /// `Profiler::show_overlay` is set.
+  pub fn update(
+    &mut self,
+    queue: &wgpu::Queue,
+    profiler: &Profiler,
+    stats: &FrameStats,
+    width: u32,
+    height: u32,
+  ) {
+    self.text.enabled = profiler.show_overlay;
+    if !profiler.show_overlay {
+      self.count = 0;
+      return;
+    }
+
+    let left = width as f32 - GRAPH_WIDTH - MARGIN;
+    let bottom = MARGIN + GRAPH_HEIGHT;
+    let quad = |x0: f32, y0: f32, x1: f32, y1: f32, color: [f32; 4]| QuadRaw {
+      rect: [
+        x0 / width as f32 * 2.0 - 1.0,
+        1.0 - y1 / height as f32 * 2.0,
+        x1 / width as f32 * 2.0 - 1.0,
+        1.0 - y0 / height as f32 * 2.0,
+      ],
+      color,
+    };
+    let y = |frame_time: f32| bottom - (frame_time / GRAPH_MAX).min(1.0) * GRAPH_HEIGHT;
+
+    let mut quads = vec![
+      quad(
+        left,
+        MARGIN,
+        left + GRAPH_WIDTH,
+        bottom,
+        [0.0, 0.0, 0.0, 0.6],
+      ),
+      quad(
+        left,
+        y(TARGET_FRAME_TIME),
+        left + GRAPH_WIDTH,
+        y(TARGET_FRAME_TIME) + 1.0,
+        [1.0, 1.0, 1.0, 0.4],
+      ),
+    ];
+    // newest frame on the right
+    let bar_width = GRAPH_WIDTH / HISTORY_FRAMES as f32;
+    let first = HISTORY_FRAMES - profiler.frame_times().len();
+    for (i, frame_time) in profiler.frame_times().enumerate() {
+      let color = if frame_time <= TARGET_FRAME_TIME {
+        [0.2, 0.9, 0.3, 0.9]
+      } else if frame_time <= SLOW_FRAME_TIME {
+        [1.0, 0.8, 0.1, 0.9]
+      } else {
+        [1.0, 0.2, 0.2, 0.9]
+      };
+      let x = left + (first + i) as f32 * bar_width;
+      quads.push(quad(x, y(frame_time), x + bar_width, bottom, color));
+    }
+
+    self.count = quads.len() as u32;
+    queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&quads));
+
+    self.text.position = (left, bottom + MARGIN).into();
+    let text = &mut self.text.text;
+    text.clear();
+    writeln!(
+      text,
+      "{:.0} fps {:>7.2} ms",
+      profiler.fps(),
+      profiler.frame_time()
+    )
+    .unwrap();
+    writeln!(text, "cpu").unwrap();
+    for section in CpuSection::ALL {
+      writeln!(
+        text,
+        " {:<14}{:>6.2} ms",
+        section.name(),
+        profiler.cpu_time(section)
+      )
+      .unwrap();
+    }
+    match profiler.gpu_times() {
+      Some(passes) => {
+        writeln!(text, "gpu").unwrap();
+        for (name, time) in passes {
+          writeln!(text, " {:<14}{:>6.2} ms", name, time).unwrap();
+        }
+      }
+      None => writeln!(text, "gpu timing unsupported").unwrap(),
+    }
+    writeln!(text, "draw calls {:>11}", stats.draw_calls).unwrap();
+    writeln!(text, "instances {:>12}", stats.instances).unwrap();
+    writeln!(text, "triangles {:>12}", stats.triangles).unwrap();
+    write!(text, "culled {:>15}", stats.culled).unwrap();
+  }
+
+  pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+    if self.count == 0 {
+      return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Profiler Overlay Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Load,
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+    render_pass.set_pipeline(&self.render_pipeline);
+    render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+    render_pass.draw(0..6, 0..self.count);
+  }
+}
+
+fn create_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+  use mesh::Vertex;
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("Overlay Shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("shader/overlay.wgsl").into()),
+  });
+  let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+    label: Some("Overlay Pipeline Layout"),
+    bind_group_layouts: &[],
+    push_constant_ranges: &[],
+  });
+
+  device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some("Overlay Pipeline"),
+    layout: Some(&layout),
+    vertex: wgpu::VertexState {
+      module: &shader,
+      entry_point: "vs_main",
+      buffers: &[QuadRaw::desc()],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: &shader,
+      entry_point: "fs_main",
+      targets: &[Some(wgpu::ColorTargetState {
+        format,
+        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+        write_mask: wgpu::ColorWrites::ALL,
+      })],
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+  })
+}
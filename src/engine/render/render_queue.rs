@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::engine::{
+  game_object::GameObject,
+  render::{
+    frustum::Frustum,
+    mesh::{LodMesh, Shape3d},
+    InstanceRaw,
+  },
+};
+
+/// instances the buffer holds before it first has to grow
+const INITIAL_CAPACITY: usize = 256;
+
+/// What the renderer drew in the last frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+  /// scene draw calls over the shadow and main passes, lights, outlines and the sky not included
+  pub draw_calls: u32,
+  /// instances drawn by those calls, an object drawn in both passes counts twice
+  pub instances: u32,
+  pub triangles: u32,
+  /// opaque objects outside the view frustum, only drawn into the shadow map
+  pub culled: u32,
+}
+
+impl FrameStats {
+  pub(crate) fn record(&mut self, instances: u32, triangles_per_instance: u32) {
+    self.draw_calls += 1;
+    self.instances += instances;
+    self.triangles += instances * triangles_per_instance;
+  }
+}
+
+/// What the opaque objects are sorted by. Objects with the same key share a single instanced
+/// draw, materials live in the instance data so they never split a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct BatchKey {
+  /// culled batches go last, the main pass stops before them and only the shadow pass draws them
+  pub culled: bool,
+  pub shape: Shape3d,
+  pub lod: usize,
+}
+
+pub(crate) struct Batch {
+  pub key: BatchKey,
+  /// range of `RenderQueue::buffer`
+  pub instances: Range<u32>,
+}
+
+/// Instances of the regular objects for the current frame, opaque ones grouped into batches
+/// followed by the transparent ones back to front. Keeps its buffers between frames and only
+/// reallocates when a frame has more instances than ever before.
+pub(crate) struct RenderQueue {
+  pub buffer: wgpu::Buffer,
+  capacity: usize,
+  instances: Vec<InstanceRaw>,
+  /// sort order of the opaque objects, indices into the slice passed to `build`
+  order: Vec<(BatchKey, usize)>,
+  pub batches: Vec<Batch>,
+}
+
+impl RenderQueue {
+  pub fn new(device: &wgpu::Device) -> Self {
+    Self {
+      buffer: create_buffer(device, INITIAL_CAPACITY),
+      capacity: INITIAL_CAPACITY,
+      instances: Vec::with_capacity(INITIAL_CAPACITY),
+      order: Vec::with_capacity(INITIAL_CAPACITY),
+      batches: vec![],
+    }
+  }
+
+  /// Clears the last frame and sorts the opaque `objects` into batches.
+  pub fn build(
+    &mut self,
+    objects: &[&GameObject],
+    meshes: &[LodMesh],
+    frustum: &Frustum,
+    camera_position: Vector3<f32>,
+  ) {
+    self.instances.clear();
+    self.order.clear();
+    self.batches.clear();
+
+    for (index, object) in objects.iter().enumerate() {
+      let (center, radius) = object.transform.bounding_sphere();
+      let key = BatchKey {
+        culled: !frustum.contains_sphere(center, radius),
+        shape: object.shape,
+        lod: select_lod(meshes, object, camera_position),
+      };
+      self.order.push((key, index));
+    }
+    self.order.sort_unstable();
+
+    for &(key, index) in &self.order {
+      let instance = self.instances.len() as u32;
+      self
+        .instances
+        .push(InstanceRaw::from_game_object(objects[index]));
+      match self.batches.last_mut() {
+        Some(batch) if batch.key == key => batch.instances.end = instance + 1,
+        _ => self.batches.push(Batch {
+          key,
+          instances: instance..instance + 1,
+        }),
+      }
+    }
+  }
+
+  /// Appends a single instance after everything queued so far and returns its index.
+  pub fn push(&mut self, object: &GameObject) -> u32 {
+    self.instances.push(InstanceRaw::from_game_object(object));
+    self.instances.len() as u32 - 1
+  }
+
+  /// Batches inside the view frustum, the ones the main pass draws.
+  pub fn visible(&self) -> impl Iterator<Item = &Batch> {
+    self.batches.iter().take_while(|batch| !batch.key.culled)
+  }
+
+  pub fn culled_count(&self) -> u32 {
+    self
+      .batches
+      .iter()
+      .filter(|batch| batch.key.culled)
+      .map(|batch| batch.instances.len() as u32)
+      .sum()
+  }
+
+  /// Uploads the instances, growing the buffer when they don't fit.
+  pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if self.instances.len() > self.capacity {
+      self.capacity = self.instances.len().next_power_of_two();
+      self.buffer = create_buffer(device, self.capacity);
+    }
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.instances));
+  }
+}
+
+/// The level of detail `object` is drawn with from `camera_position`.
+pub(crate) fn select_lod(
+  meshes: &[LodMesh],
+  object: &GameObject,
+  camera_position: Vector3<f32>,
+) -> usize {
+  let (center, radius) = object.transform.bounding_sphere();
+  meshes[object.shape.index()].select((center - camera_position).magnitude(), radius)
+}
+
+fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+  device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Instance Buffer"),
+    size: (std::mem::size_of::<InstanceRaw>() * capacity) as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
+}
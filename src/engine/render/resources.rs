@@ -1,9 +1,8 @@
 use anyhow::Ok;
-use cgmath::Vector4;
+use cgmath::Vector3;
 use std::io::{BufReader, Cursor};
-use wgpu::util::DeviceExt;
 
-use crate::engine::render::mesh;
+use crate::engine::render::{mesh, mesh_4d, texture};
 
 pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
   let path = std::path::Path::new(env!("OUT_DIR"))
@@ -14,6 +13,107 @@ pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
   Ok(txt)
 }
 
+pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
+  let path = std::path::Path::new(env!("OUT_DIR"))
+    .join("res")
+    .join(file_name);
+  let data = std::fs::read(path)?;
+
+  Ok(data)
+}
+
+/// Loads a PNG from `res/textures`.
+pub(crate) async fn load_texture(
+  file_name: &str,
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+) -> anyhow::Result<texture::Texture> {
+  let (width, height, pixels) = load_rgba8(file_name).await?;
+
+  Ok(texture::Texture::from_rgba8(
+    device, queue, width, height, &pixels, file_name,
+  ))
+}
+
+/// Loads the six square faces `px`, `nx`, `py`, `ny`, `pz` and `nz` of a cubemap from
+/// `res/textures/<name>`, returned as a texture and the average color around its horizon.
+pub(crate) async fn load_cubemap(
+  name: &str,
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+) -> anyhow::Result<(texture::Texture, [f32; 3])> {
+  let mut size = 0;
+  let mut faces = Vec::with_capacity(6);
+  for face in ["px", "nx", "py", "ny", "pz", "nz"] {
+    let (width, height, pixels) = load_rgba8(&format!("{}/{}.png", name, face)).await?;
+    if width != height || (size != 0 && width != size) {
+      anyhow::bail!(
+        "{}: cubemap faces have to be squares of the same size",
+        name
+      );
+    }
+    size = width;
+    faces.push(pixels);
+  }
+
+  // middle row of the four side faces
+  let row = (size / 2 * size * 4) as usize..((size / 2 + 1) * size * 4) as usize;
+  let side_faces = [0, 1, 4, 5];
+  let mut horizon = [0.0; 3];
+  for face in side_faces {
+    for pixel in faces[face][row.clone()].chunks_exact(4) {
+      for (channel, value) in horizon.iter_mut().zip(pixel) {
+        *channel += srgb_to_linear(*value);
+      }
+    }
+  }
+  let count = (size as usize * side_faces.len()) as f32;
+  let horizon = horizon.map(|channel| channel / count);
+
+  Ok((
+    texture::Texture::cube_from_rgba8(device, queue, size, &faces, name),
+    horizon,
+  ))
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+  let value = value as f32 / 255.0;
+  if value <= 0.04045 {
+    value / 12.92
+  } else {
+    ((value + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+/// Decodes a PNG from `res/textures` into tightly packed RGBA8 rows.
+async fn load_rgba8(file_name: &str) -> anyhow::Result<(u32, u32, Vec<u8>)> {
+  let data = load_binary(&format!("textures/{}", file_name)).await?;
+  let mut decoder = png::Decoder::new(Cursor::new(data));
+  decoder.set_transformations(png::Transformations::normalize_to_color8());
+  let mut reader = decoder.read_info()?;
+  let mut buffer = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut buffer)?;
+  let buffer = &buffer[..info.buffer_size()];
+
+  let pixels = match info.color_type {
+    png::ColorType::Rgba => buffer.to_vec(),
+    png::ColorType::Rgb => buffer
+      .chunks_exact(3)
+      .flat_map(|p| [p[0], p[1], p[2], 255])
+      .collect(),
+    png::ColorType::GrayscaleAlpha => buffer
+      .chunks_exact(2)
+      .flat_map(|p| [p[0], p[0], p[0], p[1]])
+      .collect(),
+    png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+    png::ColorType::Indexed => {
+      anyhow::bail!("{}: indexed colors should have been expanded", file_name)
+    }
+  };
+
+  Ok((info.width, info.height, pixels))
+}
+
 pub async fn load_mesh(file_name: &str, device: &wgpu::Device) -> anyhow::Result<mesh::Mesh> {
   let obj_text = load_string(file_name).await?;
   let obj_cursor = Cursor::new(obj_text);
@@ -47,31 +147,26 @@ pub async fn load_mesh(file_name: &str, device: &wgpu::Device) -> anyhow::Result
           model.mesh.normals[i * 3 + 1],
           model.mesh.normals[i * 3 + 2],
         ],
+        // OBJ puts v = 0 at the bottom, wgpu at the top
+        tex_coords: if model.mesh.texcoords.is_empty() {
+          [0.0, 0.0]
+        } else {
+          [
+            model.mesh.texcoords[i * 2],
+            1.0 - model.mesh.texcoords[i * 2 + 1],
+          ]
+        },
       })
       .collect::<Vec<_>>();
 
-    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some(&format!("{:?} Vertex Buffer", file_name)),
-      contents: bytemuck::cast_slice(&vertices),
-      usage: wgpu::BufferUsages::VERTEX,
-    });
-    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some(&format!("{:?} Index Buffer", file_name)),
-      contents: bytemuck::cast_slice(&model.mesh.indices),
-      usage: wgpu::BufferUsages::INDEX,
-    });
-    mesh::Mesh {
-      name: file_name.to_string(),
-      vertex_buffer,
-      index_buffer,
-      num_elements: model.mesh.indices.len() as u32,
-    }
+    mesh::Mesh::new(device, file_name, &vertices, &model.mesh.indices)
   };
 
   Ok(mesh)
 }
 
-pub async fn load_mesh_4d(file_name: &str) -> anyhow::Result<mesh::Mesh4d> {
+/// Loads an OBJ and extrudes it along w, see `Mesh4d::extrude`.
+pub async fn load_mesh_4d(file_name: &str, depth: f32) -> anyhow::Result<mesh_4d::Mesh4d> {
   let obj_text = load_string(file_name).await?;
   let obj_cursor = Cursor::new(obj_text);
   let mut obj_reader = BufReader::new(obj_cursor);
@@ -91,51 +186,17 @@ pub async fn load_mesh_4d(file_name: &str) -> anyhow::Result<mesh::Mesh4d> {
   .await?;
 
   let model = models.get(0).unwrap();
-  let mesh = {
-    let vertices = (0..model.mesh.positions.len() / 3)
-      .map(|i| mesh::MeshVertex {
-        position: [
-          model.mesh.positions[i * 3],
-          model.mesh.positions[i * 3 + 1],
-          model.mesh.positions[i * 3 + 2],
-        ],
-        normal: [
-          model.mesh.normals[i * 3],
-          model.mesh.normals[i * 3 + 1],
-          model.mesh.normals[i * 3 + 2],
-        ],
-      })
-      .collect::<Vec<_>>();
-
-    let mut vertices_4d = Vec::<Vector4<f32>>::new();
-    let mut normals = Vec::<[f32; 3]>::new();
-    let mut indices = model.mesh.indices.clone();
-
-    let original_indices = indices.clone();
-    for i in 0..5 {
-      for vertex in vertices.iter() {
-        let pos = Vector4::new(
-          vertex.position[0] * (f32::powf(0.8, i as f32)),
-          vertex.position[1] * (f32::powf(0.8, i as f32)),
-          vertex.position[2] * (f32::powf(0.8, i as f32)),
-          0.0,
-        );
-        vertices_4d.push(pos);
-        normals.push(vertex.normal);
-      }
-      for j in original_indices.iter() {
-        indices.push(j + vertices.len() as u32 * i);
-      }
-    }
-
-    mesh::Mesh4d {
-      name: file_name.to_string(),
-      vertices: vertices_4d,
-      normals,
-      indices,
-      angle: 0.0,
-    }
-  };
+  let positions = model
+    .mesh
+    .positions
+    .chunks_exact(3)
+    .map(|p| Vector3::new(p[0], p[1], p[2]))
+    .collect::<Vec<_>>();
 
-  Ok(mesh)
+  Ok(mesh_4d::Mesh4d::extrude(
+    file_name,
+    &positions,
+    &model.mesh.indices,
+    depth,
+  ))
 }
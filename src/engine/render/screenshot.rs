@@ -0,0 +1,166 @@
+use std::{
+  fs,
+  io::BufWriter,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail};
+
+pub const SCREENSHOT_DIRECTORY: &str = "screenshots";
+
+/// A frame read back from the GPU as tightly packed RGBA8 rows.
+pub struct Screenshot {
+  pub width: u32,
+  pub height: u32,
+  pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+  /// Copies `texture` into a mapped buffer and waits for it, so only call this outside of
+  /// the hot path. Works with the 8-bit RGBA and BGRA formats surfaces use, the texture needs
+  /// `COPY_SRC` usage.
+  pub fn capture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+  ) -> anyhow::Result<Self> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+      bail!("can't capture a texture without COPY_SRC usage");
+    }
+    let (width, height) = (texture.width(), texture.height());
+    let bgra = match texture.format() {
+      wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+      wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+      format => bail!("can't capture a {:?} texture", format),
+    };
+
+    // every row of the copy has to start on a 256 byte boundary
+    let unpadded_bytes_per_row = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Screenshot Buffer"),
+      size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("Screenshot Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+      wgpu::ImageCopyTexture {
+        texture,
+        mip_level: 0,
+        origin: wgpu::Origin3d::ZERO,
+        aspect: wgpu::TextureAspect::All,
+      },
+      wgpu::ImageCopyBuffer {
+        buffer: &buffer,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(padded_bytes_per_row),
+          rows_per_image: Some(height),
+        },
+      },
+      wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+      sender.send(result).ok();
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+      .recv()
+      .map_err(|_| anyhow!("screenshot buffer was never mapped"))??;
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+      let data = slice.get_mapped_range();
+      for row in data.chunks(padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+      }
+    }
+    buffer.unmap();
+
+    for pixel in pixels.chunks_exact_mut(4) {
+      if bgra {
+        pixel.swap(0, 2);
+      }
+      pixel[3] = 255;
+    }
+
+    Ok(Self {
+      width,
+      height,
+      pixels,
+    })
+  }
+
+  pub fn save_png(&self, path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    let file = fs::File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&self.pixels)?;
+    Ok(())
+  }
+
+  /// Saves to `directory/screenshot_<date>_<time>.png` and returns the path.
+  pub fn save_timestamped(&self, directory: &Path) -> anyhow::Result<PathBuf> {
+    let path = directory.join(format!("screenshot_{}.png", timestamp()));
+    self.save_png(&path)?;
+    Ok(path)
+  }
+}
+
+/// Current UTC time as `YYYY-MM-DD_HH-MM-SS-mmm`.
+fn timestamp() -> String {
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default();
+  let seconds = now.as_secs() as i64;
+  let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+  // days since 1970-01-01 to a civil date
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let day_of_era = z.rem_euclid(146097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!(
+    "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}",
+    year,
+    month,
+    day,
+    time / 3600,
+    time % 3600 / 60,
+    time % 60,
+    now.subsec_millis()
+  )
+}
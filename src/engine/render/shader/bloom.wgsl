@@ -0,0 +1,80 @@
+struct Bloom {
+  threshold: f32,
+  knee: f32,
+  intensity: f32,
+  _padding: f32,
+}
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> bloom: Bloom;
+@group(0) @binding(3)
+var bloom_texture: texture_2d<f32>;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// one triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = uv;
+  return out;
+}
+
+fn texel_size() -> vec2<f32> {
+  return 1.0 / vec2<f32>(textureDimensions(source));
+}
+
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+  let texel = texel_size();
+  var color = textureSample(source, source_sampler, uv + texel * vec2<f32>(-1.0, -1.0)).rgb;
+  color += textureSample(source, source_sampler, uv + texel * vec2<f32>(1.0, -1.0)).rgb;
+  color += textureSample(source, source_sampler, uv + texel * vec2<f32>(-1.0, 1.0)).rgb;
+  color += textureSample(source, source_sampler, uv + texel * vec2<f32>(1.0, 1.0)).rgb;
+  return color * 0.25;
+}
+
+@fragment
+fn fs_threshold(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = downsample(in.uv);
+  let brightness = max(color.r, max(color.g, color.b));
+  var soft = clamp(brightness - bloom.threshold + bloom.knee, 0.0, 2.0 * bloom.knee);
+  soft = soft * soft / (4.0 * bloom.knee + 0.0001);
+  let contribution = max(soft, brightness - bloom.threshold) / max(brightness, 0.0001);
+  return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+  return vec4<f32>(downsample(in.uv), 1.0);
+}
+
+// 3x3 tent filter, blended additively onto the next larger level
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+  let texel = texel_size();
+  var color = textureSample(source, source_sampler, in.uv).rgb * 4.0;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(-1.0, 0.0)).rgb * 2.0;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(1.0, 0.0)).rgb * 2.0;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(0.0, -1.0)).rgb * 2.0;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(0.0, 1.0)).rgb * 2.0;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(-1.0, -1.0)).rgb;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(1.0, -1.0)).rgb;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(-1.0, 1.0)).rgb;
+  color += textureSample(source, source_sampler, in.uv + texel * vec2<f32>(1.0, 1.0)).rgb;
+  return vec4<f32>(color / 16.0, 1.0);
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+  let scene = textureSample(source, source_sampler, in.uv).rgb;
+  let glow = textureSample(bloom_texture, source_sampler, in.uv).rgb;
+  return vec4<f32>(scene + glow * bloom.intensity, 1.0);
+}
@@ -0,0 +1,22 @@
+struct Params {
+  direction: vec2<f32>,
+  radius: f32,
+  _padding: f32,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+// 9 tap gaussian along `direction`, `radius` spreads the taps further apart
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let texel = params.direction * params.radius / vec2<f32>(textureDimensions(input_texture));
+  var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+  var color = textureSample(input_texture, input_sampler, in.uv).rgb * weights[0];
+  for (var i = 1; i < 5; i += 1) {
+    let offset = texel * f32(i);
+    color += textureSample(input_texture, input_sampler, in.uv + offset).rgb * weights[i];
+    color += textureSample(input_texture, input_sampler, in.uv - offset).rgb * weights[i];
+  }
+  return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,16 @@
+@group(0) @binding(0)
+// bound as a float texture, loading from depth textures is not supported on every backend
+var depth_texture: texture_multisampled_2d<f32>;
+
+// one triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+// copies the first sample so post-processing can read depth like without MSAA
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @builtin(frag_depth) f32 {
+  return textureLoad(depth_texture, vec2<i32>(position.xy), 0).r;
+}
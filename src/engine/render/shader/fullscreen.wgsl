@@ -0,0 +1,24 @@
+// shared by every post-process pass, the effect shader is appended to this
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(3)
+// scene depth, read with textureLoad
+var depth_texture: texture_2d<f32>;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// one triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = uv;
+  return out;
+}
+
@@ -0,0 +1,19 @@
+struct Params {
+  angle: f32,
+  _padding: vec3<f32>,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+// rotates the color around the grey axis
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(input_texture, input_sampler, in.uv).rgb;
+  let axis = vec3<f32>(0.57735);
+  let cos_angle = cos(params.angle);
+  let sin_angle = sin(params.angle);
+  let shifted = color * cos_angle
+    + cross(axis, color) * sin_angle
+    + axis * dot(axis, color) * (1.0 - cos_angle);
+  return vec4<f32>(shifted, 1.0);
+}
@@ -1,16 +1,30 @@
 struct Camera {
   view_pos: vec4<f32>,
   view_proj: mat4x4<f32>,
+  // rgb is the fog color, a is 1 when fog is enabled
+  fog_color: vec4<f32>,
+  // x is where fog starts, y where it fully covers the scene
+  fog_range: vec4<f32>,
 }
 @group(0) @binding(0)
 var<uniform> camera: Camera;
 
 struct Light {
   position: vec3<f32>,
+  kind: u32,
+  direction: vec3<f32>,
+  range: f32,
   color: vec3<f32>,
+  intensity: f32,
+  cos_inner: f32,
+  cos_outer: f32,
+}
+struct Lights {
+  count: u32,
+  lights: array<Light>,
 }
 @group(1) @binding(0)
-var<uniform> light: Light;
+var<storage, read> lights: Lights;
 
 struct VertexInput {
   @location(0) position: vec3<f32>,
@@ -24,10 +38,17 @@ struct VertexOutput {
 @vertex
 fn vs_main(
   model: VertexInput,
+  @builtin(instance_index) instance: u32,
 ) -> VertexOutput {
   let scale = 0.25;
+  let light = lights.lights[instance];
   var out: VertexOutput;
-  out.clip_position = camera.view_proj * vec4<f32>(model.position * scale + light.position, 1.0);
+  if (light.kind == 1u) {
+    // directional lights have no position to draw a marker at
+    out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+  } else {
+    out.clip_position = camera.view_proj * vec4<f32>(model.position * scale + light.position, 1.0);
+  }
   out.color = light.color;
   return out;
 }
@@ -0,0 +1,179 @@
+// shared by the scene shaders, prepended to shader.wgsl and shader_4d.wgsl
+
+struct Camera {
+  view_pos: vec4<f32>,
+  view_proj: mat4x4<f32>,
+  // rgb is the fog color, a is 1 when fog is enabled
+  fog_color: vec4<f32>,
+  // x is where fog starts, y where it fully covers the scene
+  fog_range: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Light {
+  position: vec3<f32>,
+  kind: u32,
+  direction: vec3<f32>,
+  range: f32,
+  color: vec3<f32>,
+  intensity: f32,
+  cos_inner: f32,
+  cos_outer: f32,
+}
+struct Lights {
+  count: u32,
+  lights: array<Light>,
+}
+@group(1) @binding(0)
+var<storage, read> lights: Lights;
+
+struct Shadow {
+  light_view_proj: mat4x4<f32>,
+  blob_position: vec3<f32>,
+  blob_radius: f32,
+  blob_normal: vec3<f32>,
+  light_index: u32,
+}
+@group(2) @binding(0)
+var<uniform> shadow: Shadow;
+@group(2) @binding(1)
+var shadow_map: texture_depth_2d;
+@group(2) @binding(2)
+var shadow_sampler: sampler_comparison;
+
+@group(3) @binding(0)
+var surface_texture: texture_2d<f32>;
+@group(3) @binding(1)
+var surface_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) world_normal: vec3<f32>,
+  @location(1) world_position: vec3<f32>,
+  // base color and opacity
+  @location(2) color: vec4<f32>,
+  // ambient, specular strength, shininess and emissive
+  @location(3) shading: vec4<f32>,
+  // x is 1 to skip lighting, y the texture mapping (0 none, 1 uv, 2 triplanar), z the texture scale
+  @location(4) params: vec4<f32>,
+  @location(5) tex_coords: vec2<f32>,
+};
+
+fn shadow_factor(world_position: vec3<f32>) -> f32 {
+  let clip = shadow.light_view_proj * vec4<f32>(world_position, 1.0);
+  let ndc = clip.xyz / clip.w;
+  let uv = vec2<f32>(ndc.x * 0.5 + 0.5, ndc.y * -0.5 + 0.5);
+  if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 || ndc.z > 1.0) {
+    return 1.0;
+  }
+
+  // 3x3 percentage closer filtering
+  let texel = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+  var lit = 0.0;
+  for (var x = -1; x <= 1; x += 1) {
+    for (var y = -1; y <= 1; y += 1) {
+      let offset = vec2<f32>(f32(x), f32(y)) * texel;
+      lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, ndc.z);
+    }
+  }
+  return lit / 9.0;
+}
+
+fn blob_factor(world_position: vec3<f32>) -> f32 {
+  if (shadow.blob_radius <= 0.0) {
+    return 1.0;
+  }
+  let offset = world_position - shadow.blob_position;
+  let height = dot(offset, shadow.blob_normal);
+  let radial = length(offset - shadow.blob_normal * height);
+  let inside = 1.0 - smoothstep(shadow.blob_radius * 0.6, shadow.blob_radius, radial);
+  let on_surface = 1.0 - step(0.1, abs(height));
+  return 1.0 - inside * on_surface * 0.6;
+}
+
+// base color with the surface texture multiplied in
+fn surface_color(in: VertexOutput) -> vec3<f32> {
+  // everything is sampled up front, texture sampling has to stay in uniform control flow
+  let scale = in.params.z;
+  let uv = textureSample(surface_texture, surface_sampler, in.tex_coords * scale).rgb;
+  let p = in.world_position * scale;
+  let x = textureSample(surface_texture, surface_sampler, p.zy).rgb;
+  let y = textureSample(surface_texture, surface_sampler, p.xz).rgb;
+  let z = textureSample(surface_texture, surface_sampler, p.xy).rgb;
+
+  // blend the three projections by how much the surface faces each axis
+  var weights = pow(abs(in.world_normal), vec3<f32>(4.0));
+  weights /= weights.x + weights.y + weights.z;
+  let triplanar = x * weights.x + y * weights.y + z * weights.z;
+
+  let mode = u32(in.params.y + 0.5);
+  var texel = vec3<f32>(1.0);
+  if (mode == 1u) {
+    texel = uv;
+  } else if (mode == 2u) {
+    texel = triplanar;
+  }
+  return in.color.rgb * texel;
+}
+
+// light reaching the surface from every light, before it is tinted by the base color
+fn lighting(in: VertexOutput) -> vec3<f32> {
+  let ambient_strength = in.shading.x;
+  let view_dir = normalize(camera.view_pos.xyz - in.world_position);
+
+  var result = vec3<f32>(0.0);
+  let in_light = shadow_factor(in.world_position);
+  for (var i = 0u; i < lights.count; i += 1u) {
+    let light = lights.lights[i];
+    let light_color = light.color * light.intensity;
+
+    var light_dir = -light.direction;
+    var attenuation = 1.0;
+    if (light.kind != 1u) {
+      let to_light = light.position - in.world_position;
+      let distance = length(to_light);
+      light_dir = to_light / distance;
+      if (light.range > 0.0) {
+        let falloff = clamp(1.0 - distance / light.range, 0.0, 1.0);
+        attenuation = falloff * falloff;
+      }
+      if (light.kind == 2u) {
+        let theta = dot(light_dir, -light.direction);
+        attenuation *= smoothstep(light.cos_outer, light.cos_inner, theta);
+      }
+    }
+    let half_dir = normalize(view_dir + light_dir);
+
+    let ambient_color = light_color * ambient_strength;
+
+    let diffuse_strength = max(dot(in.world_normal, light_dir), 0.0);
+    let diffuse_color = light_color * diffuse_strength;
+
+    let specular_strength = pow(max(dot(in.world_normal, half_dir), 0.0), in.shading.z) * in.shading.y;
+    let specular_color = specular_strength * light_color;
+
+    if (i == shadow.light_index) {
+      attenuation *= in_light;
+    }
+
+    result += ambient_color + (diffuse_color + specular_color) * attenuation;
+  }
+  return result * blob_factor(in.world_position);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let base_color = surface_color(in);
+  var result = base_color;
+  if (in.params.x < 0.5) {
+    result *= lighting(in);
+  }
+  result += base_color * in.shading.w;
+
+  let distance = length(camera.view_pos.xyz - in.world_position);
+  let fog = smoothstep(camera.fog_range.x, camera.fog_range.y, distance) * camera.fog_color.a;
+  result = mix(result, camera.fog_color.rgb, fog);
+
+  return vec4<f32>(result, in.color.a);
+}
@@ -0,0 +1,79 @@
+struct Camera {
+  view_pos: vec4<f32>,
+  view_proj: mat4x4<f32>,
+  // rgb is the fog color, a is 1 when fog is enabled
+  fog_color: vec4<f32>,
+  // x is where fog starts, y where it fully covers the scene
+  fog_range: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexInput {
+  @location(0) position: vec3<f32>,
+};
+
+struct InstanceInput {
+  @location(5) model_matrix_0: vec4<f32>,
+  @location(6) model_matrix_1: vec4<f32>,
+  @location(7) model_matrix_2: vec4<f32>,
+  @location(8) model_matrix_3: vec4<f32>,
+  // rgb is the color, a the width in world units
+  @location(9) color: vec4<f32>,
+}
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(
+  model: VertexInput,
+  instance: InstanceInput,
+) -> VertexOutput {
+  let model_matrix = mat4x4<f32>(
+    instance.model_matrix_0,
+    instance.model_matrix_1,
+    instance.model_matrix_2,
+    instance.model_matrix_3,
+  );
+
+  // push every corner out along the box axes, divided by the scale so the outline is equally
+  // wide on every side of a stretched platform
+  let scale = vec3<f32>(
+    length(instance.model_matrix_0.xyz),
+    length(instance.model_matrix_1.xyz),
+    length(instance.model_matrix_2.xyz),
+  );
+  let position = model.position + sign(model.position) * instance.color.a / scale;
+
+  var out: VertexOutput;
+  out.clip_position = camera.view_proj * model_matrix * vec4<f32>(position, 1.0);
+  out.color = instance.color.rgb;
+  return out;
+}
+
+// the object at its original size, for masking the inside of transparent objects
+@vertex
+fn vs_mask(
+  model: VertexInput,
+  instance: InstanceInput,
+) -> VertexOutput {
+  let model_matrix = mat4x4<f32>(
+    instance.model_matrix_0,
+    instance.model_matrix_1,
+    instance.model_matrix_2,
+    instance.model_matrix_3,
+  );
+
+  var out: VertexOutput;
+  out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+  out.color = instance.color.rgb;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,35 @@
+// flat colored rectangles drawn over the finished frame, for the profiler overlay
+
+struct QuadInput {
+  // min x, min y, max x and max y in normalized device coordinates
+  @location(0) rect: vec4<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+};
+
+// two triangles per quad, corners picked from the vertex index
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, quad: QuadInput) -> VertexOutput {
+  var corners = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, 0.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(1.0, 1.0),
+  );
+  let corner = corners[index];
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(mix(quad.rect.xy, quad.rect.zw, corner), 0.0, 1.0);
+  out.color = quad.color;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return in.color;
+}
@@ -0,0 +1,10 @@
+struct Params {
+  _padding: vec4<f32>,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return vec4<f32>(textureSample(input_texture, input_sampler, in.uv).rgb, 1.0);
+}
@@ -0,0 +1,30 @@
+struct VertexInput {
+  @location(0) position: vec4<f32>,
+  // the other two corners of the triangle, in winding order
+  @location(1) next: vec4<f32>,
+  @location(2) previous: vec4<f32>,
+};
+
+// rotates in 4D, perspective projects onto the w = 0 hyperplane, then places the result in the world
+fn project(position: vec4<f32>, rotation: mat4x4<f32>, model: mat4x4<f32>, view_distance: f32) -> vec3<f32> {
+  let rotated = rotation * position;
+  // points at or behind the 4D eye would flip or blow up
+  let scale = view_distance / max(view_distance - rotated.w, 0.01);
+  return (model * vec4<f32>(rotated.xyz * scale, 1.0)).xyz;
+}
+
+@vertex
+fn vs_main(
+  model: VertexInput,
+  instance: InstanceInput,
+) -> VertexOutput {
+  let model_matrix = model_matrix(instance);
+  let rotation = rotation_matrix(instance);
+  let view_distance = instance.view_4d.x;
+
+  let position = project(model.position, rotation, model_matrix, view_distance);
+  let next = project(model.next, rotation, model_matrix, view_distance);
+  let previous = project(model.previous, rotation, model_matrix, view_distance);
+
+  return face_output(position, next, previous, instance);
+}
@@ -1,20 +1,7 @@
-struct Camera {
-  view_pos: vec4<f32>,
-  view_proj: mat4x4<f32>,
-}
-@group(0) @binding(0)
-var<uniform> camera: Camera;
-
-struct Light {
-  position: vec3<f32>,
-  color: vec3<f32>,
-}
-@group(1) @binding(0)
-var<uniform> light: Light;
-
 struct VertexInput {
   @location(0) position: vec3<f32>,
   @location(1) normal: vec3<f32>,
+  @location(2) tex_coords: vec2<f32>,
 };
 
 struct InstanceInput {
@@ -25,16 +12,11 @@ struct InstanceInput {
   @location(9) normal_matrix_0: vec3<f32>,
   @location(10) normal_matrix_1: vec3<f32>,
   @location(11) normal_matrix_2: vec3<f32>,
-  @location(12) color: vec3<f32>,
+  @location(12) color: vec4<f32>,
+  @location(13) shading: vec4<f32>,
+  @location(14) params: vec4<f32>,
 }
 
-struct VertexOutput {
-  @builtin(position) clip_position: vec4<f32>,
-  @location(0) world_normal: vec3<f32>,
-  @location(1) world_position: vec3<f32>,
-  @location(2) color: vec3<f32>,
-};
-
 @vertex
 fn vs_main(
   model: VertexInput,
@@ -59,25 +41,8 @@ fn vs_main(
   out.world_position = world_position.xyz;
   out.clip_position = camera.view_proj * world_position;
   out.color = instance.color;
+  out.shading = instance.shading;
+  out.params = instance.params;
+  out.tex_coords = model.tex_coords;
   return out;
 }
-
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-  let ambient_strength = 0.1;
-  let ambient_color = light.color * ambient_strength;
-
-  let light_dir = normalize(light.position - in.world_position);
-  let view_dir = normalize(camera.view_pos.xyz - in.world_position);
-  let half_dir = normalize(view_dir + light_dir);
-
-  let diffuse_strength = max(dot(in.world_normal, light_dir), 0.0);
-  let diffuse_color = light.color * diffuse_strength;
-
-  let specular_strength = pow(max(dot(in.world_normal, half_dir), 0.0), 32.0);
-  let specular_color = specular_strength * light.color;
-
-  let result = (ambient_color + diffuse_color + specular_color) * in.color;
-  
-  return vec4<f32>(result, 0.0);
-}
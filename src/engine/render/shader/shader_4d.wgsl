@@ -0,0 +1,65 @@
+// shared by the 4D shaders, prepended to project_4d.wgsl and slice_4d.wgsl
+
+struct InstanceInput {
+  @location(4) model_matrix_0: vec4<f32>,
+  @location(5) model_matrix_1: vec4<f32>,
+  @location(6) model_matrix_2: vec4<f32>,
+  @location(7) model_matrix_3: vec4<f32>,
+  @location(8) rotation_0: vec4<f32>,
+  @location(9) rotation_1: vec4<f32>,
+  @location(10) rotation_2: vec4<f32>,
+  @location(11) rotation_3: vec4<f32>,
+  @location(12) color: vec4<f32>,
+  @location(13) shading: vec4<f32>,
+  @location(14) params: vec4<f32>,
+  // x is the view distance, y where the slicing hyperplane cuts the object relative to its own w
+  @location(15) view_4d: vec2<f32>,
+}
+
+fn model_matrix(instance: InstanceInput) -> mat4x4<f32> {
+  return mat4x4<f32>(
+    instance.model_matrix_0,
+    instance.model_matrix_1,
+    instance.model_matrix_2,
+    instance.model_matrix_3,
+  );
+}
+
+fn rotation_matrix(instance: InstanceInput) -> mat4x4<f32> {
+  return mat4x4<f32>(
+    instance.rotation_0,
+    instance.rotation_1,
+    instance.rotation_2,
+    instance.rotation_3,
+  );
+}
+
+// lit output for a flat shaded triangle, `position` is the corner being output
+fn face_output(
+  position: vec3<f32>,
+  next: vec3<f32>,
+  previous: vec3<f32>,
+  instance: InstanceInput,
+) -> VertexOutput {
+  // faces get bent by the projection and winding flips while rotating through w, so the
+  // normal comes from the final triangle, turned towards the camera
+  var normal = cross(next - position, previous - position);
+  if (length(normal) < 0.000001) {
+    normal = vec3<f32>(0.0, 1.0, 0.0);
+  }
+  normal = normalize(normal);
+  if (dot(normal, camera.view_pos.xyz - position) < 0.0) {
+    normal = -normal;
+  }
+
+  var out: VertexOutput;
+  out.world_normal = normal;
+  out.world_position = position;
+  out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
+  out.color = instance.color;
+  out.shading = instance.shading;
+  // the 4D meshes have no texture coordinates, triplanar mapping still works
+  out.params = instance.params;
+  out.tex_coords = vec2<f32>(0.0);
+  return out;
+}
@@ -0,0 +1,76 @@
+struct Sky {
+  inv_view_proj: mat4x4<f32>,
+  view_pos: vec4<f32>,
+  // w is the kind of sky, 1 for the gradient and 2 for the cubemap
+  zenith: vec4<f32>,
+  // w is how much of the sky is covered in stars
+  horizon: vec4<f32>,
+  ground: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> sky: Sky;
+@group(0) @binding(1)
+var sky_texture: texture_cube<f32>;
+@group(0) @binding(2)
+var sky_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) ndc: vec2<f32>,
+};
+
+// one triangle covering the whole screen on the far plane, so it only shows where the depth
+// buffer is still clear
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  let ndc = uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+  out.ndc = ndc;
+  return out;
+}
+
+// from the camera through the point on the near plane under this pixel
+fn view_direction(ndc: vec2<f32>) -> vec3<f32> {
+  let near = sky.inv_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+  return normalize(near.xyz / near.w - sky.view_pos.xyz);
+}
+
+fn hash(p: vec3<f32>) -> f32 {
+  return fract(sin(dot(p, vec3<f32>(12.9898, 78.233, 37.719))) * 43758.5453);
+}
+
+// small dots on a grid of cells around the camera, only some of the cells get one
+fn stars(direction: vec3<f32>) -> f32 {
+  let cells = 80.0;
+  let cell = floor(direction * cells);
+  if (hash(cell) > sky.horizon.w) {
+    return 0.0;
+  }
+  let jitter = vec3<f32>(hash(cell + 1.0), hash(cell + 2.0), hash(cell + 3.0)) - 0.5;
+  let center = (cell + 0.5 + jitter * 0.5) / cells;
+  let distance = length(direction - center) * cells;
+  let brightness = 0.5 + hash(cell + 4.0);
+  // fade out towards the horizon where the gradient is brightest
+  return (1.0 - smoothstep(0.0, 0.3, distance)) * brightness * smoothstep(0.0, 0.2, direction.y);
+}
+
+fn gradient(direction: vec3<f32>) -> vec3<f32> {
+  let height = direction.y;
+  if (height >= 0.0) {
+    return mix(sky.horizon.rgb, sky.zenith.rgb, sqrt(height));
+  }
+  return mix(sky.horizon.rgb, sky.ground.rgb, sqrt(min(-height * 4.0, 1.0)));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let direction = view_direction(in.ndc);
+  // sampled up front, texture sampling has to stay in uniform control flow
+  let cubemap = textureSample(sky_texture, sky_sampler, direction).rgb;
+  if (sky.zenith.w > 1.5) {
+    return vec4<f32>(cubemap, 1.0);
+  }
+  return vec4<f32>(gradient(direction) + vec3<f32>(stars(direction)), 1.0);
+}
@@ -0,0 +1,67 @@
+struct VertexInput {
+  @builtin(vertex_index) index: u32,
+  // the tetrahedron this vertex belongs to
+  @location(0) corner_0: vec4<f32>,
+  @location(1) corner_1: vec4<f32>,
+  @location(2) corner_2: vec4<f32>,
+  @location(3) corner_3: vec4<f32>,
+};
+
+// every cell is drawn as 6 vertices, a quad cut splits into points 0 1 3 and 0 3 2, a
+// triangle cut only uses the first three
+const QUAD_ORDER = array<u32, 6>(0u, 1u, 3u, 0u, 3u, 2u);
+const TRIANGLE_ORDER = array<u32, 6>(0u, 1u, 2u, 0u, 0u, 0u);
+
+@vertex
+fn vs_main(
+  model: VertexInput,
+  instance: InstanceInput,
+) -> VertexOutput {
+  let model_matrix = model_matrix(instance);
+  let rotation = rotation_matrix(instance);
+  let slice_w = instance.view_4d.y;
+
+  var corners = array<vec4<f32>, 4>(
+    rotation * model.corner_0,
+    rotation * model.corner_1,
+    rotation * model.corner_2,
+    rotation * model.corner_3,
+  );
+
+  // walk the 6 edges in a fixed order, keeping the points where they cross the hyperplane.
+  // With 4 crossings that order always goes around the quad as 0 1 3 2
+  var points: array<vec3<f32>, 4>;
+  var count = 0u;
+  for (var a = 0u; a < 4u; a += 1u) {
+    for (var b = a + 1u; b < 4u; b += 1u) {
+      let from_a = corners[a].w - slice_w;
+      let from_b = corners[b].w - slice_w;
+      if ((from_a < 0.0) != (from_b < 0.0)) {
+        let t = from_a / (from_a - from_b);
+        let point = mix(corners[a].xyz, corners[b].xyz, t);
+        points[count] = (model_matrix * vec4<f32>(point, 1.0)).xyz;
+        count += 1u;
+      }
+    }
+  }
+
+  if (count < 3u) {
+    // the hyperplane misses this cell, collapse both triangles
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    return out;
+  }
+
+  let corner = model.index % 6u;
+  var order = TRIANGLE_ORDER;
+  if (count == 4u) {
+    order = QUAD_ORDER;
+  }
+  let triangle = corner / 3u * 3u;
+  return face_output(
+    points[order[corner]],
+    points[order[triangle + (corner + 1u) % 3u]],
+    points[order[triangle + (corner + 2u) % 3u]],
+    instance,
+  );
+}
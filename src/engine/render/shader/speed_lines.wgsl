@@ -0,0 +1,34 @@
+struct Params {
+  intensity: f32,
+  time: f32,
+  aspect: f32,
+  _padding: f32,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+const LINE_COUNT: f32 = 96.0;
+
+fn hash(n: f32) -> f32 {
+  return fract(sin(n * 127.1) * 43758.5453);
+}
+
+// thin streaks around the edge of the screen, each line flickers on its own
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(input_texture, input_sampler, in.uv).rgb;
+
+  let offset = (in.uv - vec2<f32>(0.5)) * vec2<f32>(params.aspect, 1.0);
+  let angle = atan2(offset.y, offset.x) / 6.28318 + 0.5;
+  let line = floor(angle * LINE_COUNT);
+  let across = abs(fract(angle * LINE_COUNT) - 0.5) * 2.0;
+
+  let flicker = step(0.55, hash(line + floor(params.time * 12.0)));
+  let thickness = 0.15 + hash(line) * 0.25;
+  let streak = (1.0 - smoothstep(0.0, thickness, across)) * flicker;
+
+  // lines only show up away from the center
+  let edge = smoothstep(0.25, 0.75, length(offset) / max(params.aspect, 1.0) * 2.0);
+  let amount = streak * edge * params.intensity;
+  return vec4<f32>(mix(color, vec3<f32>(1.0), amount * 0.6), 1.0);
+}
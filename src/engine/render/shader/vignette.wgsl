@@ -0,0 +1,14 @@
+struct Params {
+  intensity: f32,
+  _padding: vec3<f32>,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(input_texture, input_sampler, in.uv).rgb;
+  let distance = length(in.uv - vec2<f32>(0.5)) * 1.41421;
+  let darken = smoothstep(0.4, 1.0, distance) * params.intensity;
+  return vec4<f32>(color * (1.0 - darken), 1.0);
+}
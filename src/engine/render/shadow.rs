@@ -0,0 +1,280 @@
+use cgmath::{prelude::*, Matrix4, Point3, Vector3};
+
+use crate::engine::{
+  camera::OPENGL_TO_WGPU_MATRIX,
+  render::{
+    light::{Light, LightKind},
+    mesh::{self, LodMesh, Vertex},
+    render_queue::{FrameStats, RenderQueue},
+    texture, InstanceRaw,
+  },
+};
+
+const SHADOW_MAP_SIZE: u32 = 2048;
+/// half the width of the area around the camera covered by the shadow map
+const SHADOW_EXTENT: f32 = 60.0;
+const SHADOW_DEPTH: f32 = 200.0;
+const NO_SHADOW_LIGHT: u32 = u32::MAX;
+
+/// Dark disc drawn on the surface under an object, used as a landing indicator.
+#[derive(Clone, Copy, Debug)]
+pub struct BlobShadow {
+  pub position: Vector3<f32>,
+  pub normal: Vector3<f32>,
+  pub radius: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowUniform {
+  light_view_proj: [[f32; 4]; 4],
+  blob_position: [f32; 3],
+  blob_radius: f32,
+  blob_normal: [f32; 3],
+  light_index: u32,
+}
+
+/// Shadow map rendered from the first directional light in the scene.
+pub struct ShadowState {
+  uniform: ShadowUniform,
+  buffer: wgpu::Buffer,
+  texture: texture::Texture,
+  pub bind_group: wgpu::BindGroup,
+  pub bind_group_layout: wgpu::BindGroupLayout,
+  pass_bind_group: wgpu::BindGroup,
+  render_pipeline: wgpu::RenderPipeline,
+}
+
+impl ShadowState {
+  pub fn new(device: &wgpu::Device) -> Self {
+    let uniform = ShadowUniform {
+      light_view_proj: Matrix4::identity().into(),
+      blob_position: [0.0; 3],
+      blob_radius: 0.0,
+      blob_normal: [0.0, 1.0, 0.0],
+      light_index: NO_SHADOW_LIGHT,
+    };
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Shadow Buffer"),
+      size: std::mem::size_of::<ShadowUniform>() as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let texture =
+      texture::Texture::create_shadow_texture(device, SHADOW_MAP_SIZE, "shadow_texture");
+
+    let uniform_entry = wgpu::BindGroupLayoutEntry {
+      binding: 0,
+      visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+      ty: wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+      count: None,
+    };
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[
+        uniform_entry,
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Depth,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 2,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+          count: None,
+        },
+      ],
+      label: Some("shadow_bind_group_layout"),
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &bind_group_layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::TextureView(&texture.view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 2,
+          resource: wgpu::BindingResource::Sampler(&texture.sampler),
+        },
+      ],
+      label: Some("shadow_bind_group"),
+    });
+
+    let pass_bind_group_layout =
+      device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[uniform_entry],
+        label: Some("shadow_pass_bind_group_layout"),
+      });
+
+    let pass_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &pass_bind_group_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+      label: Some("shadow_pass_bind_group"),
+    });
+
+    let render_pipeline = {
+      let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Shadow Pipeline Layout"),
+        bind_group_layouts: &[&pass_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+      let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shadow Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("shader/shadow.wgsl").into()),
+      });
+
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[mesh::MeshVertex::desc(), InstanceRaw::desc()],
+        },
+        fragment: None,
+        primitive: wgpu::PrimitiveState {
+          topology: wgpu::PrimitiveTopology::TriangleList,
+          strip_index_format: None,
+          front_face: wgpu::FrontFace::Ccw,
+          cull_mode: Some(wgpu::Face::Back),
+          polygon_mode: wgpu::PolygonMode::Fill,
+          unclipped_depth: false,
+          conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format: texture::Texture::DEPTH_FORMAT,
+          depth_write_enabled: true,
+          depth_compare: wgpu::CompareFunction::LessEqual,
+          stencil: wgpu::StencilState::default(),
+          bias: wgpu::DepthBiasState {
+            constant: 2,
+            slope_scale: 2.0,
+            clamp: 0.0,
+          },
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+      })
+    };
+
+    Self {
+      uniform,
+      buffer,
+      texture,
+      bind_group,
+      bind_group_layout,
+      pass_bind_group,
+      render_pipeline,
+    }
+  }
+
+  /// Fits the shadow map around `center` for the first directional light in `lights`.
+  pub fn update(
+    &mut self,
+    queue: &wgpu::Queue,
+    center: Point3<f32>,
+    lights: &[Light],
+    blob: Option<&BlobShadow>,
+  ) {
+    let shadow_light = lights
+      .iter()
+      .position(|light| matches!(light.kind, LightKind::Directional));
+
+    self.uniform.light_index = NO_SHADOW_LIGHT;
+    if let Some(index) = shadow_light {
+      let direction = lights[index].direction;
+      if direction.magnitude2() > 0.0 {
+        let direction = direction.normalize();
+        let up = if direction.y.abs() > 0.99 {
+          Vector3::unit_z()
+        } else {
+          Vector3::unit_y()
+        };
+        let eye = center - direction * (SHADOW_DEPTH * 0.5);
+        let view = Matrix4::look_to_rh(eye, direction, up);
+        let projection = cgmath::ortho(
+          -SHADOW_EXTENT,
+          SHADOW_EXTENT,
+          -SHADOW_EXTENT,
+          SHADOW_EXTENT,
+          0.1,
+          SHADOW_DEPTH,
+        );
+        self.uniform.light_view_proj = (OPENGL_TO_WGPU_MATRIX * projection * view).into();
+        self.uniform.light_index = index as u32;
+      }
+    }
+
+    match blob {
+      Some(blob) => {
+        self.uniform.blob_position = blob.position.into();
+        self.uniform.blob_normal = blob.normal.into();
+        self.uniform.blob_radius = blob.radius;
+      }
+      None => self.uniform.blob_radius = 0.0,
+    }
+
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+  }
+
+  /// Renders the depth of every opaque batch, culled ones included, as seen from the shadow
+  /// casting light.
+  pub(super) fn render(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    meshes: &[LodMesh],
+    render_queue: &RenderQueue,
+    stats: &mut FrameStats,
+  ) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Shadow Pass"),
+      color_attachments: &[],
+      depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+        view: &self.texture.view,
+        depth_ops: Some(wgpu::Operations {
+          load: wgpu::LoadOp::Clear(1.0),
+          store: wgpu::StoreOp::Store,
+        }),
+        stencil_ops: None,
+      }),
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+
+    if self.uniform.light_index == NO_SHADOW_LIGHT {
+      return;
+    }
+
+    render_pass.set_pipeline(&self.render_pipeline);
+    render_pass.set_bind_group(0, &self.pass_bind_group, &[]);
+    render_pass.set_vertex_buffer(1, render_queue.buffer.slice(..));
+    for batch in &render_queue.batches {
+      let mesh = meshes[batch.key.shape.index()].get(batch.key.lod);
+      render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+      render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+      render_pass.draw_indexed(0..mesh.num_elements, 0, batch.instances.clone());
+      stats.record(batch.instances.len() as u32, mesh.num_elements / 3);
+    }
+  }
+}
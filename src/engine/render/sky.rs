@@ -0,0 +1,278 @@
+use cgmath::SquareMatrix;
+use wgpu::util::DeviceExt;
+
+use crate::engine::render::{resources, texture, CameraUniform};
+
+/// What is drawn behind the scene, also decides the fog color so distant objects fade into it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Sky {
+  /// flat `GameState::background_color`, which the game cycles through the hues
+  #[default]
+  Color,
+  Gradient(SkyGradient),
+  /// six PNGs named `px`, `nx`, `py`, `ny`, `pz` and `nz` in `res/textures/<name>`
+  Cubemap(String),
+}
+
+/// Procedural sky fading from the horizon up to the zenith and down to the ground.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyGradient {
+  pub zenith: [f32; 3],
+  pub horizon: [f32; 3],
+  pub ground: [f32; 3],
+  /// chance for each patch of the sky to have a star, around 0.05 looks like a clear night
+  pub stars: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniform {
+  inv_view_proj: [[f32; 4]; 4],
+  view_position: [f32; 4],
+  /// w is 1 for a gradient and 2 for a cubemap
+  zenith: [f32; 4],
+  /// w is the star density
+  horizon: [f32; 4],
+  ground: [f32; 4],
+}
+
+struct Cubemap {
+  name: String,
+  bind_group: wgpu::BindGroup,
+  /// average color around the horizon, used as the fog color
+  horizon: [f32; 3],
+}
+
+pub struct SkyState {
+  uniform: SkyUniform,
+  buffer: wgpu::Buffer,
+  bind_group_layout: wgpu::BindGroupLayout,
+  /// bound while no cubemap is loaded
+  empty_bind_group: wgpu::BindGroup,
+  cubemap: Option<Cubemap>,
+  render_pipeline: wgpu::RenderPipeline,
+  pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl SkyState {
+  pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, sample_count: u32) -> Self {
+    let uniform = SkyUniform {
+      inv_view_proj: cgmath::Matrix4::identity().into(),
+      view_position: [0.0; 4],
+      zenith: [0.0; 4],
+      horizon: [0.0; 4],
+      ground: [0.0; 4],
+    };
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Sky Buffer"),
+      contents: bytemuck::cast_slice(&[uniform]),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some("sky_bind_group_layout"),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::Cube,
+            multisampled: false,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 2,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+          count: None,
+        },
+      ],
+    });
+
+    let empty = texture::Texture::cube_from_rgba8(device, queue, 1, &vec![vec![0; 4]; 6], "sky");
+    let empty_bind_group = create_bind_group(device, &bind_group_layout, &buffer, &empty);
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Sky Pipeline Layout"),
+      bind_group_layouts: &[&bind_group_layout],
+      push_constant_ranges: &[],
+    });
+    let render_pipeline = create_pipeline(device, &pipeline_layout, sample_count);
+
+    Self {
+      uniform,
+      buffer,
+      bind_group_layout,
+      empty_bind_group,
+      cubemap: None,
+      render_pipeline,
+      pipeline_layout,
+    }
+  }
+
+  pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+    self.render_pipeline = create_pipeline(device, &self.pipeline_layout, sample_count);
+  }
+
+  /// Uploads the sky as seen by `camera`, loading its cubemap the first time it is used.
+  /// Returns the fog color, `background` unless the sky has a horizon to fade into.
+  pub(super) fn update(
+    &mut self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    sky: &Sky,
+    camera: &CameraUniform,
+    background: [f32; 3],
+  ) -> [f32; 3] {
+    let fog_color = match sky {
+      Sky::Color => {
+        self.uniform.zenith[3] = 0.0;
+        background
+      }
+      Sky::Gradient(gradient) => {
+        let with = |color: [f32; 3], w: f32| [color[0], color[1], color[2], w];
+        self.uniform.zenith = with(gradient.zenith, 1.0);
+        self.uniform.horizon = with(gradient.horizon, gradient.stars);
+        self.uniform.ground = with(gradient.ground, 0.0);
+        gradient.horizon
+      }
+      Sky::Cubemap(name) => {
+        if self.cubemap.as_ref().map(|cubemap| &cubemap.name) != Some(name) {
+          self.cubemap = self.load_cubemap(device, queue, name);
+        }
+        match &self.cubemap {
+          Some(cubemap) => {
+            self.uniform.zenith[3] = 2.0;
+            cubemap.horizon
+          }
+          None => {
+            self.uniform.zenith[3] = 0.0;
+            background
+          }
+        }
+      }
+    };
+
+    let view_proj = cgmath::Matrix4::from(camera.view_proj);
+    self.uniform.inv_view_proj = view_proj.invert().unwrap_or(view_proj).into();
+    self.uniform.view_position = camera.view_position;
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    fog_color
+  }
+
+  fn load_cubemap(
+    &self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    name: &str,
+  ) -> Option<Cubemap> {
+    match pollster::block_on(resources::load_cubemap(name, device, queue)) {
+      Ok((texture, horizon)) => Some(Cubemap {
+        name: name.to_string(),
+        bind_group: create_bind_group(device, &self.bind_group_layout, &self.buffer, &texture),
+        horizon,
+      }),
+      Err(e) => {
+        eprintln!("failed to load the {} skybox: {:?}", name, e);
+        None
+      }
+    }
+  }
+
+  /// Fills everything the scene left empty, a flat colored sky is already the clear color.
+  pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+    if self.uniform.zenith[3] == 0.0 {
+      return;
+    }
+    let bind_group = match &self.cubemap {
+      Some(cubemap) => &cubemap.bind_group,
+      None => &self.empty_bind_group,
+    };
+    render_pass.set_pipeline(&self.render_pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+fn create_bind_group(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  buffer: &wgpu::Buffer,
+  texture: &texture::Texture,
+) -> wgpu::BindGroup {
+  device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: Some("sky_bind_group"),
+    layout,
+    entries: &[
+      wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      },
+      wgpu::BindGroupEntry {
+        binding: 1,
+        resource: wgpu::BindingResource::TextureView(&texture.view),
+      },
+      wgpu::BindGroupEntry {
+        binding: 2,
+        resource: wgpu::BindingResource::Sampler(&texture.sampler),
+      },
+    ],
+  })
+}
+
+fn create_pipeline(
+  device: &wgpu::Device,
+  layout: &wgpu::PipelineLayout,
+  sample_count: u32,
+) -> wgpu::RenderPipeline {
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("Sky Shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("shader/sky.wgsl").into()),
+  });
+
+  device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some("Sky Pipeline"),
+    layout: Some(layout),
+    vertex: wgpu::VertexState {
+      module: &shader,
+      entry_point: "vs_main",
+      buffers: &[],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: &shader,
+      entry_point: "fs_main",
+      targets: &[Some(wgpu::ColorTargetState {
+        format: texture::Texture::HDR_FORMAT,
+        blend: Some(wgpu::BlendState::REPLACE),
+        write_mask: wgpu::ColorWrites::ALL,
+      })],
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    // the triangle sits on the far plane, it passes only where nothing was drawn
+    depth_stencil: Some(wgpu::DepthStencilState {
+      format: texture::Texture::DEPTH_FORMAT,
+      depth_write_enabled: false,
+      depth_compare: wgpu::CompareFunction::LessEqual,
+      stencil: wgpu::StencilState::default(),
+      bias: wgpu::DepthBiasState::default(),
+    }),
+    multisample: wgpu::MultisampleState {
+      count: sample_count,
+      mask: !0,
+      alpha_to_coverage_enabled: false,
+    },
+    multiview: None,
+  })
+}
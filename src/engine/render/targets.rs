@@ -0,0 +1,245 @@
+use super::texture;
+
+/// Offscreen color and depth the scene is drawn into, sized by the render scale. With MSAA the
+/// scene is drawn into multisampled copies that are resolved into `color` and `depth`.
+pub struct RenderTargets {
+  pub width: u32,
+  pub height: u32,
+  pub sample_count: u32,
+  pub color: texture::Texture,
+  pub depth: texture::Texture,
+  msaa: Option<MsaaTargets>,
+  /// whether the multisampled depth can be sampled to resolve it, see `can_resolve_depth`
+  depth_resolvable: bool,
+  resolve_layout: wgpu::BindGroupLayout,
+  resolve_pipeline: wgpu::RenderPipeline,
+}
+
+struct MsaaTargets {
+  color: texture::Texture,
+  depth: texture::Texture,
+  resolve_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl RenderTargets {
+  pub fn new(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+    depth_resolvable: bool,
+  ) -> Self {
+    let resolve_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+          multisampled: true,
+          view_dimension: wgpu::TextureViewDimension::D2,
+          sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        },
+        count: None,
+      }],
+      label: Some("depth_resolve_bind_group_layout"),
+    });
+
+    let resolve_pipeline = {
+      let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Depth Resolve Pipeline Layout"),
+        bind_group_layouts: &[&resolve_layout],
+        push_constant_ranges: &[],
+      });
+      let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Depth Resolve Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("shader/depth_resolve.wgsl").into()),
+      });
+
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth Resolve Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format: texture::Texture::DEPTH_FORMAT,
+          depth_write_enabled: true,
+          depth_compare: wgpu::CompareFunction::Always,
+          stencil: wgpu::StencilState::default(),
+          bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+      })
+    };
+
+    let (color, depth, msaa) = create_textures(
+      device,
+      &resolve_layout,
+      width,
+      height,
+      sample_count,
+      depth_resolvable,
+    );
+
+    Self {
+      width,
+      height,
+      sample_count,
+      color,
+      depth,
+      msaa,
+      depth_resolvable,
+      resolve_layout,
+      resolve_pipeline,
+    }
+  }
+
+  pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32, sample_count: u32) {
+    self.width = width;
+    self.height = height;
+    self.sample_count = sample_count;
+    (self.color, self.depth, self.msaa) = create_textures(
+      device,
+      &self.resolve_layout,
+      width,
+      height,
+      sample_count,
+      self.depth_resolvable,
+    );
+  }
+
+  /// The view to draw the scene into and the texture it resolves to, if any.
+  pub fn color_attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+    match &self.msaa {
+      Some(msaa) => (&msaa.color.view, Some(&self.color.view)),
+      None => (&self.color.view, None),
+    }
+  }
+
+  pub fn depth_attachment(&self) -> &wgpu::TextureView {
+    match &self.msaa {
+      Some(msaa) => &msaa.depth.view,
+      None => &self.depth.view,
+    }
+  }
+
+  /// Copies the multisampled depth into `depth`, color is resolved by the render pass itself.
+  /// Where the depth can't be resolved `depth` is only cleared to the far plane.
+  pub fn resolve_depth(&self, encoder: &mut wgpu::CommandEncoder) {
+    let Some(msaa) = &self.msaa else {
+      return;
+    };
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Depth Resolve Pass"),
+      color_attachments: &[],
+      depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+        view: &self.depth.view,
+        depth_ops: Some(wgpu::Operations {
+          load: wgpu::LoadOp::Clear(1.0),
+          store: wgpu::StoreOp::Store,
+        }),
+        stencil_ops: None,
+      }),
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+    if let Some(resolve_bind_group) = &msaa.resolve_bind_group {
+      render_pass.set_pipeline(&self.resolve_pipeline);
+      render_pass.set_bind_group(0, resolve_bind_group, &[]);
+      render_pass.draw(0..3, 0..1);
+    }
+  }
+}
+
+/// Sample counts both the HDR color and depth formats can be rendered with.
+pub fn supported_sample_counts(adapter: &wgpu::Adapter, features: wgpu::Features) -> Vec<u32> {
+  if !features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+    // always available without the adapter specific format features
+    return vec![1, 4];
+  }
+
+  let color = adapter
+    .get_texture_format_features(texture::Texture::HDR_FORMAT)
+    .flags;
+  let depth = adapter
+    .get_texture_format_features(texture::Texture::DEPTH_FORMAT)
+    .flags;
+  [1, 2, 4, 8, 16]
+    .into_iter()
+    .filter(|&count| {
+      count == 1 || (color.sample_count_supported(count) && depth.sample_count_supported(count))
+    })
+    .collect()
+}
+
+/// The GL backend can't draw into multisampled textures that are also bindable, only into
+/// renderbuffers, so the depth resolve shader has nothing to read there.
+pub fn can_resolve_depth(adapter: &wgpu::Adapter) -> bool {
+  adapter.get_info().backend != wgpu::Backend::Gl
+}
+
+fn create_textures(
+  device: &wgpu::Device,
+  resolve_layout: &wgpu::BindGroupLayout,
+  width: u32,
+  height: u32,
+  sample_count: u32,
+  depth_resolvable: bool,
+) -> (texture::Texture, texture::Texture, Option<MsaaTargets>) {
+  let color = texture::Texture::create_render_target(
+    device,
+    width,
+    height,
+    texture::Texture::HDR_FORMAT,
+    "hdr_texture",
+  );
+  let depth = texture::Texture::create_depth_texture(device, width, height, "depth_texture");
+
+  let msaa = (sample_count > 1).then(|| {
+    let color = texture::Texture::create_multisampled_target(
+      device,
+      width,
+      height,
+      texture::Texture::HDR_FORMAT,
+      sample_count,
+      false,
+      "msaa_hdr_texture",
+    );
+    let depth = texture::Texture::create_multisampled_target(
+      device,
+      width,
+      height,
+      texture::Texture::DEPTH_FORMAT,
+      sample_count,
+      depth_resolvable,
+      "msaa_depth_texture",
+    );
+    let resolve_bind_group = depth_resolvable.then(|| {
+      device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: resolve_layout,
+        entries: &[wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&depth.view),
+        }],
+        label: Some("depth_resolve_bind_group"),
+      })
+    });
+
+    MsaaTargets {
+      color,
+      depth,
+      resolve_bind_group,
+    }
+  });
+
+  (color, depth, msaa)
+}
@@ -6,15 +6,281 @@ pub struct Texture {
 
 impl Texture {
   pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+  pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 
-  pub fn create_depth_texture(
+  /// Color texture that can be rendered to and then sampled by a later pass.
+  pub fn create_render_target(
     device: &wgpu::Device,
-    config: &wgpu::SurfaceConfiguration,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
     label: &str,
   ) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(label),
+      size: wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      address_mode_u: wgpu::AddressMode::ClampToEdge,
+      address_mode_v: wgpu::AddressMode::ClampToEdge,
+      address_mode_w: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    Self {
+      texture,
+      view,
+      sampler,
+    }
+  }
+
+  /// Sampled sRGB texture from tightly packed RGBA8 rows, with a full mip chain so it doesn't
+  /// shimmer in the distance. Repeats in both directions.
+  pub fn from_rgba8(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    label: &str,
+  ) -> Self {
+    let mip_level_count = width.max(height).ilog2() + 1;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(label),
+      size: wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+
+    let mut level = (width, height, pixels.to_vec());
+    for mip_level in 0..mip_level_count {
+      let (width, height, ref pixels) = level;
+      queue.write_texture(
+        wgpu::ImageCopyTexture {
+          texture: &texture,
+          mip_level,
+          origin: wgpu::Origin3d::ZERO,
+          aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(width * 4),
+          rows_per_image: Some(height),
+        },
+        wgpu::Extent3d {
+          width,
+          height,
+          depth_or_array_layers: 1,
+        },
+      );
+      level = downsample(width, height, pixels);
+    }
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      address_mode_u: wgpu::AddressMode::Repeat,
+      address_mode_v: wgpu::AddressMode::Repeat,
+      address_mode_w: wgpu::AddressMode::Repeat,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    Self {
+      texture,
+      view,
+      sampler,
+    }
+  }
+
+  /// Cubemap from six square RGBA8 faces in the order +x, -x, +y, -y, +z, -z.
+  pub fn cube_from_rgba8(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    size: u32,
+    faces: &[Vec<u8>],
+    label: &str,
+  ) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(label),
+      size: wgpu::Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: 6,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+
+    for (layer, pixels) in faces.iter().enumerate() {
+      queue.write_texture(
+        wgpu::ImageCopyTexture {
+          texture: &texture,
+          mip_level: 0,
+          origin: wgpu::Origin3d {
+            x: 0,
+            y: 0,
+            z: layer as u32,
+          },
+          aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(size * 4),
+          rows_per_image: Some(size),
+        },
+        wgpu::Extent3d {
+          width: size,
+          height: size,
+          depth_or_array_layers: 1,
+        },
+      );
+    }
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+      dimension: Some(wgpu::TextureViewDimension::Cube),
+      ..Default::default()
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      address_mode_u: wgpu::AddressMode::ClampToEdge,
+      address_mode_v: wgpu::AddressMode::ClampToEdge,
+      address_mode_w: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    Self {
+      texture,
+      view,
+      sampler,
+    }
+  }
+
+  /// Layout for sampling a texture from `from_rgba8` in the fragment shader, the texture at
+  /// binding 0 and its sampler at 1.
+  pub fn bind_group_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some(label),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+          count: None,
+        },
+      ],
+    })
+  }
+
+  pub fn bind_group(
+    &self,
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    label: &str,
+  ) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some(label),
+      layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&self.view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(&self.sampler),
+        },
+      ],
+    })
+  }
+
+  /// Color or depth target with several samples per pixel, resolved before it is sampled.
+  /// `bindable` targets can also be read in a shader with `texture_multisampled_2d`.
+  pub fn create_multisampled_target(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    bindable: bool,
+    label: &str,
+  ) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(label),
+      size: wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: if bindable {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+      } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+      },
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+    Self {
+      texture,
+      view,
+      sampler,
+    }
+  }
+
+  pub fn create_shadow_texture(device: &wgpu::Device, size: u32, label: &str) -> Self {
+    Self::create_depth_texture(device, size, size, label)
+  }
+
+  pub fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32, label: &str) -> Self {
     let size = wgpu::Extent3d {
-      width: config.width,
-      height: config.height,
+      width,
+      height,
       depth_or_array_layers: 1,
     };
     let desc = wgpu::TextureDescriptor {
@@ -49,3 +315,21 @@ impl Texture {
     }
   }
 }
+
+/// Halves an RGBA8 image by averaging 2x2 blocks, odd edges reuse their last row or column.
+fn downsample(width: u32, height: u32, pixels: &[u8]) -> (u32, u32, Vec<u8>) {
+  let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+  let mut half = Vec::with_capacity((half_width * half_height * 4) as usize);
+  for y in 0..half_height {
+    for x in 0..half_width {
+      for channel in 0..4 {
+        let sample = |dx: u32, dy: u32| {
+          let (sx, sy) = ((x * 2 + dx).min(width - 1), (y * 2 + dy).min(height - 1));
+          pixels[((sy * width + sx) * 4 + channel) as usize] as u32
+        };
+        half.push(((sample(0, 0) + sample(1, 0) + sample(0, 1) + sample(1, 1) + 2) / 4) as u8);
+      }
+    }
+  }
+  (half_width, half_height, half)
+}
@@ -0,0 +1,49 @@
+#[derive(Default)]
+pub struct Settings {
+  pub video: VideoSettings,
+  pub accessibility: AccessibilitySettings,
+}
+
+pub struct VideoSettings {
+  /// vertical field of view in degrees when standing still
+  pub fov: f32,
+  /// field of view the camera widens to at top speed
+  pub max_fov: f32,
+  /// far clip plane, fog fades everything out before it
+  pub draw_distance: f32,
+  pub fog: bool,
+  /// samples per pixel, rounded down to what the GPU supports (1 turns MSAA off)
+  pub msaa_samples: u32,
+  /// resolution the scene is rendered at relative to the window, 0.5 to 2.0
+  pub render_scale: f32,
+  pub camera_shake: bool,
+  pub speed_lines: bool,
+  pub bloom: bool,
+  /// brightness above which pixels start to glow
+  pub bloom_threshold: f32,
+  pub bloom_intensity: f32,
+}
+
+impl Default for VideoSettings {
+  fn default() -> Self {
+    Self {
+      fov: 60.0,
+      max_fov: 80.0,
+      draw_distance: 1000.0,
+      fog: true,
+      msaa_samples: 4,
+      render_scale: 1.0,
+      camera_shake: true,
+      speed_lines: true,
+      bloom: true,
+      bloom_threshold: 1.0,
+      bloom_intensity: 0.6,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct AccessibilitySettings {
+  /// rotates the hue of the whole screen, in degrees
+  pub hue_shift: f32,
+}
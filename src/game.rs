@@ -1,20 +1,45 @@
+use cgmath::{EuclideanSpace, Vector3};
 use engine::{physics::input::Input, GameObject, GameState, Scene};
 use project_shmove::engine::{
   self,
-  physics::collision::{Collision, Tag},
+  physics::collision::{Collision, EventStatus, QueryFilter, Tag, TriggerEvent},
   render::color::Color,
-  TextObject, Time,
+  Highlight, Light, TextObject, Time,
 };
+use winit::event::{MouseButton, VirtualKeyCode};
 
-use self::camera::CameraController;
+use self::{
+  camera::CameraController,
+  fly_camera::FlyCameraController,
+  level::{Level, PadKind},
+};
 
 mod camera;
+mod fly_camera;
+mod level;
 mod player;
 
+/// switches between the player and the free-fly camera
+const FREE_CAMERA_KEY: VirtualKeyCode = VirtualKeyCode::F1;
+/// move the camera along w, through the slices of 4D objects
+const W_FORWARD_KEY: VirtualKeyCode = VirtualKeyCode::E;
+const W_BACK_KEY: VirtualKeyCode = VirtualKeyCode::Q;
+/// w units per second
+const W_SPEED: f32 = 1.0;
+/// toggles the selection of the platform under the crosshair in the free camera
+const SELECT_BUTTON: MouseButton = MouseButton::Left;
+/// how far away platforms can be hovered
+const SELECT_DISTANCE: f32 = 200.0;
+/// shows frame timings and what the renderer drew
+const PROFILER_KEY: VirtualKeyCode = VirtualKeyCode::F3;
+
 pub struct GameScene {
   camera_controller: CameraController,
+  fly_camera: FlyCameraController,
+  /// the fly camera is in control and the player is frozen
+  free_camera: bool,
   player_controller: player::Controller,
-  platforms: Vec<GameObject>,
+  level: Level,
   fps_text: TextObject,
 }
 
@@ -22,22 +47,57 @@ impl GameScene {
   pub fn new() -> Self {
     Self {
       camera_controller: CameraController::new(1.0),
+      fly_camera: FlyCameraController::new(1.0),
+      free_camera: false,
       player_controller: player::Controller::new(),
-      platforms: Vec::<GameObject>::new(),
+      level: Level::new(),
       fps_text: TextObject::default(),
     }
   }
 
   fn create_platforms(&mut self, collision: &mut Collision) {
-    #[rustfmt::skip] {
-      self.platforms.push(GameObject::new((0.0, 0.0, 0.0),(0.0, 0.0, 0.0),(10.0, 1.0, 500.0), [1.0, 0.0, 0.0], Tag::Platform));
-      self.platforms.push(GameObject::new((30.0, 0.0, 0.0),(10.0, 0.0, 0.0),(5.0, 2.0, 5.0), [0.0, 1.0, 0.0], Tag::Platform));
-      self.platforms.push(GameObject::new((30.0, 0.0, 15.0),(0.0, 0.0, 0.0),(5.0, 0.5, 5.0), [0.0, 0.0, 1.0], Tag::Platform));
-      self.platforms.push(GameObject::new((35.0, 0.0, 32.0),(0.0, 0.0, 60.0),(4.0, 0.5, 5.0), [0.0, 1.0, 0.5], Tag::Platform));
-      self.platforms.push(GameObject::new((30.0, 2.0, 60.0),(-20.0, 0.0, 0.0),(5.0, 0.5, 5.0), [0.0, 0.5, 1.0], Tag::Platform));
+    self.level = Level::load("level1.lvl").unwrap();
+    self.level.register_collision(collision);
+  }
+
+  /// Outlines the platform the free camera looks at, clicking selects or deselects it.
+  fn update_highlight(&mut self, game: &mut GameState, input: &Input) {
+    for platform in self.level.platforms.iter_mut() {
+      if platform.highlight == Highlight::Hovered {
+        platform.highlight = Highlight::None;
+      }
+    }
+    if !self.free_camera {
+      return;
+    }
+
+    let (sin_pitch, cos_pitch) = game.camera.pitch.0.sin_cos();
+    let (sin_yaw, cos_yaw) = game.camera.yaw.0.sin_cos();
+    let forward = Vector3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw);
+    let Some(hit) = game.collision.raycast(
+      game.camera.position.to_vec(),
+      forward,
+      SELECT_DISTANCE,
+      &QueryFilter::tags(&[Tag::Platform]),
+    ) else {
+      return;
     };
-    for platform in self.platforms.iter_mut() {
-      platform.register_collision(collision);
+    let Some(platform) = self
+      .level
+      .platforms
+      .iter_mut()
+      .find(|platform| platform.collision_handle == hit.handle)
+    else {
+      return;
+    };
+
+    if input.mouse_button_just_pressed(SELECT_BUTTON) {
+      platform.highlight = match platform.highlight {
+        Highlight::Selected => Highlight::None,
+        _ => Highlight::Selected,
+      };
+    } else if platform.highlight == Highlight::None {
+      platform.highlight = Highlight::Hovered;
     }
   }
 }
@@ -50,38 +110,124 @@ impl Scene for GameScene {
       .register_collision(&mut game.collision);
 
     self.create_platforms(&mut game.collision);
+    game.objects_4d.append(&mut self.level.objects_4d);
+    if let Some(sky) = self.level.sky.take() {
+      game.sky = sky;
+    }
+
+    game
+      .lights
+      .push(Light::directional((-0.3, -1.0, 0.5), [0.8, 0.8, 0.8]));
+    game
+      .lights
+      .push(Light::point((2.0, 5.0, 2.0), [1.0, 1.0, 1.0]));
 
     self.player_controller.start();
 
     self.fps_text.size = 20.0;
   }
 
+  fn on_trigger(&mut self, _game: &mut GameState, event: &TriggerEvent) {
+    let (EventStatus::Enter, Tag::Player) = (event.status, event.other_tag) else {
+      return;
+    };
+    let player = &mut self.player_controller;
+
+    if let Some(checkpoint) = self
+      .level
+      .checkpoints
+      .iter()
+      .find(|checkpoint| checkpoint.collision_handle == event.trigger)
+    {
+      player.respawn_point = checkpoint.transform.position;
+    }
+
+    if let Some(pad) = self
+      .level
+      .pads
+      .iter()
+      .find(|pad| pad.game_object.collision_handle == event.trigger)
+    {
+      match pad.kind {
+        PadKind::Boost {
+          direction,
+          speed,
+          speed_limit,
+        } => player.boost(direction, speed, speed_limit),
+        PadKind::Launch { velocity } => player.launch(velocity),
+        PadKind::Ring => player.refresh_jump(),
+      }
+    }
+  }
+
   fn update(&mut self, game: &mut GameState, input: &Input, time: &Time) {
     game
       .background_color
       .set_hue(time.elapsed_time as f64 * 25.0);
 
-    self
-      .player_controller
-      .update(game, input, &self.camera_controller, time);
+    for platform in self.level.moving_platforms.iter_mut() {
+      platform.update(&mut game.collision, time.delta_time);
+    }
 
-    self
-      .camera_controller
-      .set_pos(self.player_controller.camera_position);
-    self
-      .camera_controller
-      .update(&mut game.camera, input.get_mouse_speed(), time);
+    if input.key_pressed(FREE_CAMERA_KEY) {
+      self.free_camera = !self.free_camera;
+      game.screen_effects = Default::default();
+      for platform in self.level.platforms.iter_mut() {
+        platform.highlight = Highlight::None;
+      }
+    }
+
+    if self.free_camera {
+      self.fly_camera.update(&mut game.camera, input, time);
+    } else {
+      self
+        .player_controller
+        .update(game, input, &self.camera_controller, time);
+
+      self
+        .camera_controller
+        .set_pos(self.player_controller.camera_position);
+      self
+        .camera_controller
+        .set_speed(self.player_controller.speed_fraction());
+      self
+        .camera_controller
+        .land(self.player_controller.landing_speed);
+      self.camera_controller.update(game, input, time);
+
+      if input.key_down(W_FORWARD_KEY) {
+        game.camera.w += W_SPEED * time.delta_time;
+      }
+      if input.key_down(W_BACK_KEY) {
+        game.camera.w -= W_SPEED * time.delta_time;
+      }
+    }
+
+    self.update_highlight(game, input);
 
     self.fps_text.color = Color::from_hsv(time.elapsed_time as f64 * 50.0, 1.0, 1.0);
-    self.fps_text.text = String::from(format!("{}", (1.0 / time.delta_time) as i32));
+    self.fps_text.text = format!("{}", game.profiler.fps().round() as i32);
+
+    if input.key_pressed(PROFILER_KEY) {
+      game.profiler.show_overlay = !game.profiler.show_overlay;
+    }
   }
 
   fn get_objects(&mut self) -> (Vec<&mut GameObject>, Vec<&engine::TextObject>) {
     let mut objects = Vec::<&mut GameObject>::new();
     objects.push(&mut self.player_controller.game_object);
-    for platform in self.platforms.iter_mut() {
+    for platform in self.level.platforms.iter_mut() {
       objects.push(platform);
     }
+    for pad in self.level.pads.iter_mut() {
+      objects.push(&mut pad.game_object);
+    }
+    for platform in self.level.moving_platforms.iter_mut() {
+      objects.push(&mut platform.game_object);
+    }
+    for checkpoint in self.level.checkpoints.iter_mut() {
+      objects.push(checkpoint);
+    }
     (
       objects,
       vec![&self.fps_text, &self.player_controller.debug_text],
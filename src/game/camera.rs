@@ -1,31 +1,95 @@
-use cgmath::{InnerSpace, Rad, Vector2, Vector3, Zero};
+use cgmath::{Deg, InnerSpace, Rad, Vector2, Vector3, Zero};
 use std::f32::consts::FRAC_PI_2;
 
-use project_shmove::engine::{Camera, Time};
+use project_shmove::engine::{
+  physics::{
+    collision::{QueryFilter, Tag},
+    input::Input,
+  },
+  settings::VideoSettings,
+  Camera, GameState, Time,
+};
+use winit::event::VirtualKeyCode;
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
 
+const TOGGLE_MODE_KEY: VirtualKeyCode = VirtualKeyCode::V;
+
+/// distance from the player to the third-person camera when nothing is in the way
+const ARM_LENGTH: f32 = 8.0;
+/// the arm pivots this far above the player's position
+const ARM_HEIGHT: f32 = 1.5;
+/// radius of the sphere swept along the arm, keeps the near plane out of walls
+const ARM_PROBE_RADIUS: f32 = 0.4;
+/// how quickly the pivot catches up with the player
+const FOLLOW_SMOOTHING: f32 = 12.0;
+/// how quickly the arm extends again after being pushed in by a wall
+const ARM_RETURN_SMOOTHING: f32 = 4.0;
+
+/// how quickly the field of view catches up with the speed, higher is snappier
+const FOV_SMOOTHING: f32 = 6.0;
+
+/// falling faster than this shakes the camera when landing
+const HARD_LANDING_SPEED: f32 = 30.0;
+/// landing speed that gives full trauma
+const MAX_LANDING_SPEED: f32 = 70.0;
+/// trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+const SHAKE_FREQUENCY: f32 = 25.0;
+const MAX_SHAKE_ANGLE: f32 = 0.04;
+const MAX_SHAKE_OFFSET: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+  FirstPerson,
+  /// orbits the player on a spring arm that shortens when it would clip into a platform
+  ThirdPerson,
+}
+
 pub struct CameraController {
+  pub mode: CameraMode,
   sensitivity: f32,
   position: Vector3<f32>,
   pub forward: Vector3<f32>,
   pub right: Vector3<f32>,
+  /// 0..1, how close the player is to top speed
+  speed: f32,
+  fov: f32,
+  trauma: f32,
+  /// yaw and pitch added by the shake last frame, removed again before mouse look
+  shake: Vector2<f32>,
+  /// lagged point the third-person arm hangs from
+  pivot: Vector3<f32>,
+  arm_length: f32,
 }
 
 impl CameraController {
   pub fn new(sensitivity: f32) -> Self {
     Self {
+      mode: CameraMode::FirstPerson,
       sensitivity,
       position: Vector3::zero(),
       forward: Vector3::zero(),
       right: Vector3::zero(),
+      speed: 0.0,
+      fov: VideoSettings::default().fov,
+      trauma: 0.0,
+      shake: Vector2::zero(),
+      pivot: Vector3::zero(),
+      arm_length: ARM_LENGTH,
     }
   }
 
-  pub fn update(&mut self, camera: &mut Camera, mouse_speed: Vector2<f32>, time: &Time) {
-    camera.position.x = self.position.x;
-    camera.position.z = self.position.z;
-    camera.position.y = self.position.y;
+  pub fn update(&mut self, game: &mut GameState, input: &Input, time: &Time) {
+    if input.key_pressed(TOGGLE_MODE_KEY) {
+      self.toggle_mode();
+    }
+
+    let camera = &mut game.camera;
+    let mouse_speed = input.get_mouse_speed();
+
+    camera.yaw -= Rad(self.shake.x);
+    camera.pitch -= Rad(self.shake.y);
 
     camera.yaw += Rad(mouse_speed.x) * self.sensitivity * time.delta_time;
     camera.pitch += Rad(-mouse_speed.y) * self.sensitivity * time.delta_time;
@@ -39,9 +103,106 @@ impl CameraController {
     let (yaw_sin, yaw_cos) = camera.yaw.0.sin_cos();
     self.forward = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
     self.right = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
+
+    match self.mode {
+      CameraMode::FirstPerson => {
+        camera.position.x = self.position.x;
+        camera.position.z = self.position.z;
+        camera.position.y = self.position.y;
+      }
+      CameraMode::ThirdPerson => self.update_arm(game, time.delta_time),
+    }
+
+    self.update_fov(&mut game.camera, &game.settings.video, time.delta_time);
+    self.update_shake(&mut game.camera, &game.settings.video, time);
+  }
+
+  pub fn toggle_mode(&mut self) {
+    self.mode = match self.mode {
+      CameraMode::FirstPerson => {
+        self.pivot = self.position + Vector3::unit_y() * ARM_HEIGHT;
+        self.arm_length = 0.0;
+        CameraMode::ThirdPerson
+      }
+      CameraMode::ThirdPerson => CameraMode::FirstPerson,
+    };
   }
 
   pub fn set_pos(&mut self, pos: Vector3<f32>) {
     self.position = pos;
   }
+
+  /// `speed` goes from 0 at normal running speed to 1 at top speed.
+  pub fn set_speed(&mut self, speed: f32) {
+    self.speed = speed.clamp(0.0, 1.0);
+  }
+
+  /// Adds trauma (0..1), the shake strength is trauma squared so small bumps stay subtle.
+  pub fn add_trauma(&mut self, amount: f32) {
+    self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+  }
+
+  /// Shakes the camera when the player hits the ground faster than `HARD_LANDING_SPEED`.
+  pub fn land(&mut self, fall_speed: f32) {
+    if fall_speed > HARD_LANDING_SPEED {
+      self.add_trauma((fall_speed - HARD_LANDING_SPEED) / (MAX_LANDING_SPEED - HARD_LANDING_SPEED));
+    }
+  }
+
+  fn update_arm(&mut self, game: &mut GameState, dt: f32) {
+    let target = self.position + Vector3::unit_y() * ARM_HEIGHT;
+    self.pivot += (target - self.pivot) * (1.0 - (-FOLLOW_SMOOTHING * dt).exp());
+
+    let (sin_pitch, cos_pitch) = game.camera.pitch.0.sin_cos();
+    let (sin_yaw, cos_yaw) = game.camera.yaw.0.sin_cos();
+    let back = -Vector3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw);
+
+    let allowed = game
+      .collision
+      .sphere_cast(
+        self.pivot,
+        ARM_PROBE_RADIUS,
+        back,
+        ARM_LENGTH,
+        &QueryFilter::tags(&[Tag::Platform]),
+      )
+      .map_or(ARM_LENGTH, |hit| hit.distance);
+
+    // pull in straight away so the camera never ends up inside a wall, ease back out
+    if allowed < self.arm_length {
+      self.arm_length = allowed;
+    } else {
+      self.arm_length += (allowed - self.arm_length) * (1.0 - (-ARM_RETURN_SMOOTHING * dt).exp());
+    }
+
+    let position = self.pivot + back * self.arm_length;
+    game.camera.position = (position.x, position.y, position.z).into();
+  }
+
+  fn update_fov(&mut self, camera: &mut Camera, settings: &VideoSettings, dt: f32) {
+    let target = settings.fov + (settings.max_fov - settings.fov) * self.speed;
+    self.fov += (target - self.fov) * (1.0 - (-FOV_SMOOTHING * dt).exp());
+    camera.fovy = Deg(self.fov).into();
+  }
+
+  fn update_shake(&mut self, camera: &mut Camera, settings: &VideoSettings, time: &Time) {
+    self.trauma = (self.trauma - TRAUMA_DECAY * time.delta_time).max(0.0);
+    if !settings.camera_shake || self.trauma <= 0.0 {
+      self.shake = Vector2::zero();
+      return;
+    }
+
+    let shake = self.trauma * self.trauma;
+    let t = time.elapsed_time * SHAKE_FREQUENCY;
+    self.shake = Vector2::new(noise(t, 0.0), noise(t, 10.0)) * MAX_SHAKE_ANGLE * shake;
+    camera.yaw += Rad(self.shake.x);
+    camera.pitch += Rad(self.shake.y);
+    camera.position +=
+      Vector3::new(noise(t, 20.0), noise(t, 30.0), noise(t, 40.0)) * MAX_SHAKE_OFFSET * shake;
+  }
+}
+
+/// Smooth value in -1..1 that wanders around, `seed` picks a different wave.
+fn noise(t: f32, seed: f32) -> f32 {
+  ((t + seed).sin() + (t * 2.3 + seed * 1.7).sin() * 0.5) / 1.5
 }
@@ -0,0 +1,80 @@
+use cgmath::{InnerSpace, Rad, Vector3, Zero};
+use std::f32::consts::FRAC_PI_2;
+
+use project_shmove::engine::{physics::input::Input, Camera, Time};
+use winit::event::VirtualKeyCode;
+
+const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+
+const DEFAULT_SPEED: f32 = 20.0;
+const MIN_SPEED: f32 = 1.0;
+const MAX_SPEED: f32 = 500.0;
+/// speed is multiplied by this for every step of the scroll wheel
+const SCROLL_SPEED_STEP: f32 = 1.25;
+const SPRINT_MULTIPLIER: f32 = 4.0;
+
+/// Free-fly camera that moves independently of the player, used for debugging and editing.
+pub struct FlyCameraController {
+  sensitivity: f32,
+  pub speed: f32,
+}
+
+impl FlyCameraController {
+  pub fn new(sensitivity: f32) -> Self {
+    Self {
+      sensitivity,
+      speed: DEFAULT_SPEED,
+    }
+  }
+
+  /// WASD to move along the view direction, Q and E to move down and up, shift to go faster
+  /// and scroll to change the base speed.
+  pub fn update(&mut self, camera: &mut Camera, input: &Input, time: &Time) {
+    let mouse_speed = input.get_mouse_speed();
+    camera.yaw += Rad(mouse_speed.x) * self.sensitivity * time.delta_time;
+    camera.pitch += Rad(-mouse_speed.y) * self.sensitivity * time.delta_time;
+
+    if camera.pitch < -Rad(SAFE_FRAC_PI_2) {
+      camera.pitch = -Rad(SAFE_FRAC_PI_2);
+    } else if camera.pitch > Rad(SAFE_FRAC_PI_2) {
+      camera.pitch = Rad(SAFE_FRAC_PI_2);
+    }
+
+    self.speed =
+      (self.speed * SCROLL_SPEED_STEP.powf(-input.get_scroll_speed())).clamp(MIN_SPEED, MAX_SPEED);
+
+    let (sin_pitch, cos_pitch) = camera.pitch.0.sin_cos();
+    let (sin_yaw, cos_yaw) = camera.yaw.0.sin_cos();
+    let forward = Vector3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize();
+    let right = Vector3::new(-sin_yaw, 0.0, cos_yaw).normalize();
+
+    let mut direction = Vector3::zero();
+    if input.key_down(VirtualKeyCode::W) {
+      direction += forward;
+    }
+    if input.key_down(VirtualKeyCode::S) {
+      direction -= forward;
+    }
+    if input.key_down(VirtualKeyCode::D) {
+      direction += right;
+    }
+    if input.key_down(VirtualKeyCode::A) {
+      direction -= right;
+    }
+    if input.key_down(VirtualKeyCode::E) {
+      direction += Vector3::unit_y();
+    }
+    if input.key_down(VirtualKeyCode::Q) {
+      direction -= Vector3::unit_y();
+    }
+    if direction.is_zero() {
+      return;
+    }
+
+    let mut speed = self.speed;
+    if input.key_down(VirtualKeyCode::LShift) {
+      speed *= SPRINT_MULTIPLIER;
+    }
+    camera.position += direction.normalize() * speed * time.delta_time;
+  }
+}
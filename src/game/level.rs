@@ -0,0 +1,367 @@
+use anyhow::{anyhow, bail};
+use cgmath::{Vector3, Zero};
+use project_shmove::engine::{
+  physics::{
+    collision::{Collision, Tag},
+    platform::{AnimatedPlatform, Easing, LoopMode, Waypoint},
+  },
+  render::resources,
+  GameObject, Material, Object4d, Shape4d, Sky, SkyGradient, TextureMapping, View4d,
+};
+
+const BOOST_COLOR: [f32; 3] = [1.0, 1.0, 0.0];
+const LAUNCH_COLOR: [f32; 3] = [0.0, 1.0, 1.0];
+const RING_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+const CHECKPOINT_COLOR: [f32; 3] = [0.4, 1.0, 0.6];
+/// checkpoints are drawn as faint volumes so the player can see where they trigger
+const CHECKPOINT_OPACITY: f32 = 0.2;
+const PAD_GLOW: f32 = 1.5;
+/// platforms get one grid cell per world unit, the grid texture is 4 cells wide
+const PLATFORM_TEXTURE_SCALE: f32 = 0.25;
+
+pub enum PadKind {
+  /// adds `speed` in `direction`, never pushing the player past `speed_limit`
+  Boost {
+    direction: Vector3<f32>,
+    speed: f32,
+    speed_limit: Option<f32>,
+  },
+  /// sets the vertical velocity of the player
+  Launch { velocity: f32 },
+  /// gives the player their jump back while in the air
+  Ring,
+}
+
+pub struct Pad {
+  pub game_object: GameObject,
+  pub kind: PadKind,
+}
+
+/// A platform following waypoints and/or spinning.
+pub struct MovingPlatform {
+  pub game_object: GameObject,
+  pub animation: AnimatedPlatform,
+}
+
+impl MovingPlatform {
+  pub fn update(&mut self, collision: &mut Collision, dt: f32) {
+    self.animation.update(
+      collision,
+      self.game_object.collision_handle,
+      &mut self.game_object.transform,
+      dt,
+    );
+  }
+}
+
+pub struct Level {
+  pub platforms: Vec<GameObject>,
+  /// updated by the scene every frame
+  pub moving_platforms: Vec<MovingPlatform>,
+  pub checkpoints: Vec<GameObject>,
+  pub pads: Vec<Pad>,
+  /// sliced 4D shapes, handed over to `GameState::objects_4d` when the level starts
+  pub objects_4d: Vec<Object4d>,
+  /// replaces `GameState::sky` when the level starts, if set
+  pub sky: Option<Sky>,
+}
+
+impl Level {
+  pub fn new() -> Self {
+    Self {
+      platforms: vec![],
+      moving_platforms: vec![],
+      checkpoints: vec![],
+      pads: vec![],
+      objects_4d: vec![],
+      sky: None,
+    }
+  }
+
+  /// Loads a level from `res/levels`, one object per line:
+  ///
+  /// ```text
+  /// platform    position rotation scale color
+  /// moving      position rotation scale color [duration [position duration]...]
+  /// checkpoint  position scale
+  /// boost       position scale direction speed [speed_limit]
+  /// launch      position scale vertical_velocity
+  /// ring        position scale
+  /// tesseract   position scale rotation_4d w color
+  /// sky         zenith_color horizon_color ground_color [stars=0.05]
+  /// sky         cubemap=name
+  /// ```
+  ///
+  /// A moving platform starts at `position` and travels through the positions after it, every
+  /// `duration` is the seconds it takes to get to the next one. It is shaped by the options
+  /// `easing=linear|ease_in|ease_out|ease_in_out`, `loop=loop|once|ping_pong` and `spin=x,y,z`
+  /// in degrees per second, by default it moves linearly, loops and doesn't spin.
+  ///
+  /// `rotation_4d` is the xw, yw and zw rotation in degrees and `w` the tesseract's position
+  /// along w, it is only visible while the camera's w is within its extent there.
+  ///
+  /// Anything but a checkpoint can end with material options, see `Material::set_option`:
+  /// `ambient=0.2 specular=0.5 shininess=64 emissive=1 opacity=0.5 unlit`
+  ///
+  /// Platforms are textured with a triplanar grid unless they set `texture=none`, anything
+  /// else can opt in with `texture=triplanar` or `texture=uv` and `texture_scale=`.
+  pub fn load(file_name: &str) -> anyhow::Result<Self> {
+    let text = pollster::block_on(resources::load_string(&format!("levels/{}", file_name)))?;
+    let mut level = Self::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let mut tokens = line.split_whitespace();
+      let kind = tokens.next().unwrap();
+      let (options, values): (Vec<_>, Vec<_>) =
+        tokens.partition(|token| token.starts_with(|c: char| c.is_ascii_alphabetic()));
+      let values = values
+        .iter()
+        .map(|token| token.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("{}:{}: {}", file_name, line_number + 1, e))?;
+      let expect = |count: std::ops::RangeInclusive<usize>| {
+        if count.contains(&values.len()) {
+          Ok(())
+        } else {
+          Err(anyhow!(
+            "{}:{}: `{}` expects {:?} values, found {}",
+            file_name,
+            line_number + 1,
+            kind,
+            count,
+            values.len()
+          ))
+        }
+      };
+      let v = |i: usize| (values[i], values[i + 1], values[i + 2]);
+      let apply_material = |material: &mut Material| {
+        options
+          .iter()
+          .try_for_each(|option| material.set_option(option))
+          .map_err(|e| anyhow!("{}:{}: {}", file_name, line_number + 1, e))
+      };
+
+      match kind {
+        "platform" => {
+          expect(12..=12)?;
+          let color = [values[9], values[10], values[11]];
+          let mut platform = GameObject::new(v(0), v(3), v(6), color, Tag::Platform);
+          platform.material.texture = TextureMapping::Triplanar;
+          platform.material.texture_scale = PLATFORM_TEXTURE_SCALE;
+          apply_material(&mut platform.material)?;
+          level.platforms.push(platform);
+        }
+        "moving" => {
+          if values.len() != 12 && (values.len() < 13 || (values.len() - 13) % 4 != 0) {
+            bail!(
+              "{}:{}: `moving` expects 12 values, or 13 followed by 4 per waypoint, found {}",
+              file_name,
+              line_number + 1,
+              values.len()
+            );
+          }
+          let color = [values[9], values[10], values[11]];
+          let mut game_object = GameObject::new(v(0), v(3), v(6), color, Tag::Platform);
+          let mut easing = Easing::Linear;
+          let mut loop_mode = LoopMode::Loop;
+          let mut spin = Vector3::zero();
+          for option in &options {
+            match option.split_once('=') {
+              Some(("easing", value)) => {
+                easing = match value {
+                  "linear" => Easing::Linear,
+                  "ease_in" => Easing::EaseIn,
+                  "ease_out" => Easing::EaseOut,
+                  "ease_in_out" => Easing::EaseInOut,
+                  _ => bail!(
+                    "{}:{}: unknown easing `{}`",
+                    file_name,
+                    line_number + 1,
+                    value
+                  ),
+                }
+              }
+              Some(("loop", value)) => {
+                loop_mode = match value {
+                  "loop" => LoopMode::Loop,
+                  "once" => LoopMode::Once,
+                  "ping_pong" => LoopMode::PingPong,
+                  _ => bail!(
+                    "{}:{}: unknown loop mode `{}`",
+                    file_name,
+                    line_number + 1,
+                    value
+                  ),
+                }
+              }
+              Some(("spin", value)) => {
+                let speeds = value
+                  .split(',')
+                  .map(|speed| speed.parse::<f32>())
+                  .collect::<Result<Vec<_>, _>>()
+                  .map_err(|e| anyhow!("{}:{}: {}", file_name, line_number + 1, e))?;
+                let [x, y, z] = speeds[..] else {
+                  bail!(
+                    "{}:{}: `spin` expects x,y,z, found `{}`",
+                    file_name,
+                    line_number + 1,
+                    value
+                  );
+                };
+                spin = Vector3::new(x, y, z);
+              }
+              _ => game_object
+                .material
+                .set_option(option)
+                .map_err(|e| anyhow!("{}:{}: {}", file_name, line_number + 1, e))?,
+            }
+          }
+
+          let mut waypoints = vec![];
+          if values.len() > 12 {
+            waypoints.push(Waypoint::new(v(0), values[12], easing));
+            for i in (13..values.len()).step_by(4) {
+              waypoints.push(Waypoint::new(v(i), values[i + 3], easing));
+            }
+          }
+          let mut animation = AnimatedPlatform::new(waypoints, loop_mode);
+          animation.spin = spin;
+          animation.place(&mut game_object.transform);
+          level.moving_platforms.push(MovingPlatform {
+            game_object,
+            animation,
+          });
+        }
+        "checkpoint" => {
+          expect(6..=6)?;
+          let mut checkpoint = trigger(v(0), v(3), CHECKPOINT_COLOR);
+          checkpoint.material.opacity = CHECKPOINT_OPACITY;
+          checkpoint.material.unlit = true;
+          level.checkpoints.push(checkpoint);
+        }
+        "boost" => {
+          expect(10..=11)?;
+          let mut game_object = pad(v(0), v(3), BOOST_COLOR);
+          apply_material(&mut game_object.material)?;
+          level.pads.push(Pad {
+            game_object,
+            kind: PadKind::Boost {
+              direction: v(6).into(),
+              speed: values[9],
+              speed_limit: values.get(10).copied(),
+            },
+          });
+        }
+        "launch" => {
+          expect(7..=7)?;
+          let mut game_object = pad(v(0), v(3), LAUNCH_COLOR);
+          apply_material(&mut game_object.material)?;
+          level.pads.push(Pad {
+            game_object,
+            kind: PadKind::Launch {
+              velocity: values[6],
+            },
+          });
+        }
+        "ring" => {
+          expect(6..=6)?;
+          let mut game_object = pad(v(0), v(3), RING_COLOR);
+          apply_material(&mut game_object.material)?;
+          level.pads.push(Pad {
+            game_object,
+            kind: PadKind::Ring,
+          });
+        }
+        "tesseract" => {
+          expect(13..=13)?;
+          let (xw, yw, zw) = v(6);
+          let mut tesseract = Object4d::new(
+            Shape4d::Tesseract,
+            v(0),
+            v(3),
+            [values[10], values[11], values[12]],
+          );
+          tesseract.view = View4d::Slice;
+          tesseract.rotate_4d(xw, yw, zw);
+          tesseract.w = values[9];
+          apply_material(&mut tesseract.material)?;
+          level.objects_4d.push(tesseract);
+        }
+        "sky" => {
+          let mut cubemap = None;
+          let mut stars = 0.0;
+          for option in &options {
+            match option.split_once('=') {
+              Some(("cubemap", name)) => cubemap = Some(name.to_string()),
+              Some(("stars", value)) => {
+                stars = value
+                  .parse::<f32>()
+                  .map_err(|e| anyhow!("{}:{}: {}", file_name, line_number + 1, e))?
+              }
+              _ => bail!(
+                "{}:{}: unknown sky option `{}`",
+                file_name,
+                line_number + 1,
+                option
+              ),
+            }
+          }
+
+          level.sky = Some(match cubemap {
+            Some(name) => {
+              expect(0..=0)?;
+              Sky::Cubemap(name)
+            }
+            None => {
+              expect(9..=9)?;
+              Sky::Gradient(SkyGradient {
+                zenith: [values[0], values[1], values[2]],
+                horizon: [values[3], values[4], values[5]],
+                ground: [values[6], values[7], values[8]],
+                stars,
+              })
+            }
+          });
+        }
+        _ => bail!(
+          "{}:{}: unknown object `{}`",
+          file_name,
+          line_number + 1,
+          kind
+        ),
+      }
+    }
+
+    Ok(level)
+  }
+
+  pub fn register_collision(&mut self, collision: &mut Collision) {
+    for platform in self.platforms.iter_mut() {
+      platform.register_collision(collision);
+    }
+    for platform in self.moving_platforms.iter_mut() {
+      platform.game_object.register_collision(collision);
+    }
+    for checkpoint in self.checkpoints.iter_mut() {
+      checkpoint.register_collision(collision);
+    }
+    for pad in self.pads.iter_mut() {
+      pad.game_object.register_collision(collision);
+    }
+  }
+}
+
+fn trigger(position: (f32, f32, f32), scale: (f32, f32, f32), color: [f32; 3]) -> GameObject {
+  GameObject::new(position, (0.0, 0.0, 0.0), scale, color, Tag::Trigger)
+}
+
+fn pad(position: (f32, f32, f32), scale: (f32, f32, f32), color: [f32; 3]) -> GameObject {
+  let mut game_object = trigger(position, scale, color);
+  game_object.material.emissive = PAD_GLOW;
+  game_object
+}
@@ -1,9 +1,11 @@
-use cgmath::{Vector2, Vector3, Zero};
+use cgmath::{InnerSpace, Vector2, Vector3, Zero};
 use project_shmove::engine::{
   physics::{
-    collision::{EventStatus, Tag},
+    character::CharacterController,
+    collision::{QueryFilter, Tag},
     input::Input,
   },
+  render::shadow::BlobShadow,
   Color, GameObject, GameState, TextObject, Time,
 };
 use winit::event::VirtualKeyCode;
@@ -22,6 +24,13 @@ const SPRINT_SPEED: f32 = 15.0;
 
 const SPEED_LIMIT: f32 = 30.0;
 
+/// how dark the screen edges get at `SPEED_LIMIT`
+const VIGNETTE_STRENGTH: f32 = 0.6;
+/// speed lines fade in from here up to `SPEED_LIMIT`
+const SPEED_LINES_START: f32 = 20.0;
+
+const LANDING_INDICATOR_DISTANCE: f32 = 200.0;
+
 // const CROUCH_JUMP: f32 = 15.0;
 const NORMAL_JUMP: f32 = 20.0;
 const SPRINT_JUMP_BOOST: f32 = 2.0;
@@ -36,12 +45,20 @@ const SPRINT_JUMP_BOOST: f32 = 2.0;
 pub struct Controller {
   pub game_object: GameObject,
   pub camera_position: Vector3<f32>,
+  pub respawn_point: Vector3<f32>,
+  /// how fast the player was falling on the frame they landed, 0 otherwise
+  pub landing_speed: f32,
+  character: CharacterController,
   grounded: bool,
   velocity: Vector3<f32>,
+  /// velocity of the platform last stood on, from how far it carried the player, kept on jumping off
+  platform_velocity: Vector3<f32>,
   direction: Vector2<f32>,
   speed: f32,
   input_direction: Vector2<f32>,
   jump_pressed: bool,
+  jump_just_pressed: bool,
+  air_jump: bool,
   crouch_pressed: bool,
   crouch_held: bool,
   pub debug_text: TextObject,
@@ -58,12 +75,18 @@ impl Controller {
         Tag::Player,
       ),
       camera_position: Vector3::new(0.0, 5.0, 0.0),
+      respawn_point: Vector3::new(0.0, 4.0, 0.0),
+      landing_speed: 0.0,
+      character: CharacterController::new(),
       grounded: false,
       velocity: Vector3::zero(),
+      platform_velocity: Vector3::zero(),
       direction: Vector2::zero(),
       speed: 0.0,
       input_direction: Vector2::zero(),
       jump_pressed: false,
+      jump_just_pressed: false,
+      air_jump: false,
       crouch_pressed: false,
       crouch_held: false,
       debug_text: TextObject::default(),
@@ -87,9 +110,14 @@ impl Controller {
     self.debug_text.text += "\n";
 
     self.update_position(game, time.delta_time);
+    self.update_landing_indicator(game);
     self.update_input(input, camera);
     self.update_velocity(time);
 
+    game.screen_effects.vignette = self.speed_fraction() * VIGNETTE_STRENGTH;
+    game.screen_effects.speed_lines =
+      ((self.speed - SPEED_LINES_START) / (SPEED_LIMIT - SPEED_LINES_START)).clamp(0.0, 1.0);
+
     // if let Crouching | CrouchWalking | Sliding(_) | SpeedSliding(_) = self.movement_state {
     //   self.game_object.transform.scale.y = CROUCHED_HEIGHT;
     // } else {
@@ -99,76 +127,107 @@ impl Controller {
 
     if self.game_object.transform.position.y < -50.0 {
       self.velocity = Vector3::zero();
+      self.platform_velocity = Vector3::zero();
       self.speed = 0.0;
       self.input_direction = Vector2::zero();
       self.direction = Vector2::zero();
-      self.game_object.transform.position = Vector3::new(0.0, 4.0, 0.0);
+      self.game_object.transform.position = self.respawn_point;
     }
 
     // println!("{}", self.debug_text.text);
   }
 
-  fn update_position(&mut self, game: &mut GameState, dt: f32) {
-    let horizontal_vel = Vector3::new(self.velocity.x * dt, 0.0, self.velocity.z * dt);
-
-    self.game_object.transform.position += horizontal_vel;
-    game.collision.update_object(&mut self.game_object);
-    if let EventStatus::Enter = self.game_object.collision.status {
-      if !horizontal_vel.is_zero() {
-        self.game_object.transform.position -= horizontal_vel;
-
-        let toi = game.collision.get_toi(
-          &mut self.game_object.transform,
-          horizontal_vel,
-          self.game_object.collision.other_handle,
-          0.02,
-        );
-        self.game_object.transform.position += horizontal_vel * toi;
-
-        let normal_vel = cgmath::InnerSpace::normalize(horizontal_vel);
-        let mut parallel_direction = normal_vel
-          - self.game_object.collision.normal
-            * cgmath::dot(self.game_object.collision.normal, normal_vel);
-
-        if !parallel_direction.is_zero() {
-          parallel_direction = cgmath::InnerSpace::normalize(parallel_direction);
-
-          self.game_object.transform.position +=
-            parallel_direction * (cgmath::InnerSpace::magnitude(horizontal_vel) * (1.0 - toi));
-        }
-      }
+  /// Adds `amount` of horizontal speed in `direction`, up to `speed_limit` (or `SPEED_LIMIT`).
+  pub fn boost(&mut self, direction: Vector3<f32>, amount: f32, speed_limit: Option<f32>) {
+    let boost = Vector2::new(direction.x, direction.z);
+    if boost.is_zero() {
+      return;
     }
-    game.collision.update_object(&mut self.game_object);
-    while let EventStatus::Stay = self.game_object.collision.status {
-      self.game_object.transform.position.y += 0.02;
-      game.collision.update_object(&mut self.game_object);
+
+    let current = if self.direction.is_zero() {
+      Vector2::zero()
+    } else {
+      self.direction.normalize() * self.speed
+    };
+    let velocity = current + boost.normalize() * amount;
+    if velocity.is_zero() {
+      return;
     }
 
-    let vertical_vel = Vector3::new(0.0, self.velocity.y * dt, 0.0);
-    self.game_object.transform.position += vertical_vel;
-    game.collision.update_object(&mut self.game_object);
-    if let EventStatus::Enter = self.game_object.collision.status {
-      self.game_object.transform.position -= vertical_vel;
-      let toi = game.collision.get_toi(
-        &mut self.game_object.transform,
-        vertical_vel,
-        self.game_object.collision.other_handle,
-        0.02,
-      );
-      self.game_object.transform.position += vertical_vel * toi;
+    let limit = speed_limit.unwrap_or(SPEED_LIMIT).max(self.speed);
+    self.direction = velocity.normalize();
+    self.speed = velocity.magnitude().min(limit);
+  }
+
+  /// 0 at sprinting speed, 1 at `SPEED_LIMIT`.
+  pub fn speed_fraction(&self) -> f32 {
+    ((self.speed - SPRINT_SPEED) / (SPEED_LIMIT - SPRINT_SPEED)).clamp(0.0, 1.0)
+  }
+
+  pub fn launch(&mut self, vertical_velocity: f32) {
+    self.velocity.y = vertical_velocity;
+    self.grounded = false;
+  }
+
+  pub fn refresh_jump(&mut self) {
+    self.air_jump = true;
+  }
+
+  fn update_position(&mut self, game: &mut GameState, dt: f32) {
+    let inherited = if self.grounded {
+      Vector3::zero()
+    } else {
+      Vector3::new(self.platform_velocity.x, 0.0, self.platform_velocity.z)
+    };
+    let result = self.character.move_object(
+      &mut game.collision,
+      self.game_object.collision_handle,
+      &mut self.game_object.transform,
+      &mut self.game_object.collision,
+      (self.velocity + inherited) * dt,
+    );
+
+    self.landing_speed = 0.0;
+    if result.grounded {
+      if !self.grounded {
+        self.landing_speed = -self.velocity.y;
+      }
+      if dt > 0.0 {
+        self.platform_velocity = result.carried / dt;
+      }
       self.velocity.y = -5.0;
       self.grounded = true;
     } else {
+      if self.grounded {
+        self.velocity.y += self.platform_velocity.y.max(0.0);
+      }
+      if result.hit_ceiling && self.velocity.y > 0.0 {
+        self.velocity.y = 0.0;
+      }
       self.velocity.y -= GRAVITY;
       self.grounded = false;
     }
-    game.collision.update_object(&mut self.game_object);
+  }
+
+  fn update_landing_indicator(&mut self, game: &mut GameState) {
+    let hit = game.collision.raycast(
+      self.game_object.transform.position,
+      -Vector3::unit_y(),
+      LANDING_INDICATOR_DISTANCE,
+      &QueryFilter::tags(&[Tag::Platform]),
+    );
+    game.blob_shadow = hit.map(|hit| BlobShadow {
+      position: hit.point,
+      normal: hit.normal,
+      radius: self.game_object.transform.scale.x,
+    });
   }
 
   fn update_input(&mut self, input: &Input, camera: &CameraController) {
     let mut direction = Vector3::zero();
 
     self.jump_pressed = input.key_held(VirtualKeyCode::Space);
+    self.jump_just_pressed = input.key_pressed(VirtualKeyCode::Space);
     self.crouch_pressed = input.key_pressed(VirtualKeyCode::LShift);
     self.crouch_held = input.key_held(VirtualKeyCode::LShift);
 
@@ -206,9 +265,14 @@ impl Controller {
       }
     }
 
-    if self.grounded && self.jump_pressed {
+    if self.grounded {
+      self.air_jump = false;
+    }
+
+    if (self.grounded && self.jump_pressed) || (self.air_jump && self.jump_just_pressed) {
       self.velocity.y = NORMAL_JUMP;
       self.speed += SPRINT_JUMP_BOOST;
+      self.air_jump = false;
     }
 
     self.debug_text.text += &format!(
@@ -0,0 +1,548 @@
+//! Renders fixed scenes with the headless renderer and compares them against the reference
+//! images in `tests/golden`. A missing reference is recorded from the current output, set
+//! `UPDATE_GOLDEN=1` to re-record all of them after an intended visual change.
+//!
+//! Skipped when there is no graphics adapter, not even a software one.
+
+use std::path::{Path, PathBuf};
+
+use project_shmove::engine::{
+  physics::collision::Tag,
+  render::{screenshot::Screenshot, State},
+  Camera, Color, FrameStats, GameObject, GameState, Highlight, Light, Material, Object4d, Shape3d,
+  Shape4d, Sky, SkyGradient, TextureMapping, View4d,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+/// per channel difference that still counts as the same pixel
+const CHANNEL_TOLERANCE: u8 = 8;
+/// fraction of pixels allowed to differ by more than `CHANNEL_TOLERANCE`
+const PIXEL_TOLERANCE: f32 = 0.005;
+
+fn camera() -> Camera {
+  Camera::new((-8.0, 6.0, 0.0), cgmath::Deg(0.0), cgmath::Deg(-30.0))
+}
+
+fn game_state() -> GameState {
+  let mut game = GameState::new();
+  game.camera = camera();
+  game.background_color = Color::from_rgb(0.1, 0.1, 0.15);
+  // speed lines and the like depend on time, keep the output reproducible
+  game.settings.video.speed_lines = false;
+  // sample positions and resolve filters differ between drivers, `msaa_edges` covers MSAA
+  game.settings.video.msaa_samples = 1;
+  game
+}
+
+/// `game_state` lit by the sun most scenes share.
+fn lit_game_state() -> GameState {
+  let mut game = game_state();
+  game
+    .lights
+    .push(Light::directional((-0.3, -1.0, 0.5), [0.8, 0.8, 0.8]));
+  game
+}
+
+fn platform(
+  position: (f32, f32, f32),
+  rotation: (f32, f32, f32),
+  scale: (f32, f32, f32),
+  color: [f32; 3],
+) -> GameObject {
+  GameObject::new(position, rotation, scale, color, Tag::Platform)
+}
+
+/// The ground everything stands on, with its top at y = 0.5.
+fn floor(color: [f32; 3]) -> GameObject {
+  platform((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (10.0, 0.5, 10.0), color)
+}
+
+/// Renders the scene and compares it with the golden image `name`.
+fn assert_renders_golden(name: &str, game: &GameState, objects: Vec<GameObject>) {
+  if let Some(actual) = render(game, objects) {
+    assert_matches_golden(name, &actual);
+  }
+}
+
+fn render(game: &GameState, objects: Vec<GameObject>) -> Option<Screenshot> {
+  render_with_stats(game, objects).map(|(screenshot, _)| screenshot)
+}
+
+fn render_with_stats(
+  game: &GameState,
+  mut objects: Vec<GameObject>,
+) -> Option<(Screenshot, FrameStats)> {
+  let mut state = match pollster::block_on(State::new_headless(WIDTH, HEIGHT, &game.camera)) {
+    Ok(state) => state,
+    Err(e) => {
+      eprintln!("skipping golden image test: {}", e);
+      return None;
+    }
+  };
+
+  state.update_clear_color(&game.background_color);
+  state.update(game, objects.iter_mut().collect(), vec![]);
+  let screenshot = state.capture_frame().unwrap();
+  Some((screenshot, state.frame_stats()))
+}
+
+fn golden_path(name: &str) -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("golden")
+    .join(format!("{}.png", name))
+}
+
+fn load_png(path: &Path) -> Screenshot {
+  let decoder = png::Decoder::new(std::fs::File::open(path).unwrap());
+  let mut reader = decoder.read_info().unwrap();
+  let mut pixels = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut pixels).unwrap();
+  assert_eq!(info.color_type, png::ColorType::Rgba);
+  pixels.truncate(info.buffer_size());
+
+  Screenshot {
+    width: info.width,
+    height: info.height,
+    pixels,
+  }
+}
+
+fn assert_matches_golden(name: &str, actual: &Screenshot) {
+  let path = golden_path(name);
+  if !path.exists() || std::env::var_os("UPDATE_GOLDEN").is_some() {
+    actual.save_png(&path).unwrap();
+    eprintln!("recorded golden image {}", path.display());
+    return;
+  }
+
+  let expected = load_png(&path);
+  assert_eq!(
+    (actual.width, actual.height),
+    (expected.width, expected.height),
+    "{} changed size",
+    name
+  );
+
+  let differing = actual
+    .pixels
+    .chunks_exact(4)
+    .zip(expected.pixels.chunks_exact(4))
+    .filter(|(a, b)| {
+      a.iter()
+        .zip(b.iter())
+        .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+    })
+    .count();
+  let fraction = differing as f32 / (actual.width * actual.height) as f32;
+
+  if fraction > PIXEL_TOLERANCE {
+    let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}_actual.png", name));
+    actual.save_png(&actual_path).unwrap();
+    panic!(
+      "{} differs from the golden image in {:.2}% of pixels, output saved to {}",
+      name,
+      fraction * 100.0,
+      actual_path.display()
+    );
+  }
+}
+
+fn lit_platforms_scene() -> (GameState, Vec<GameObject>) {
+  let mut game = lit_game_state();
+  game
+    .lights
+    .push(Light::point((0.0, 3.0, 2.0), [1.0, 0.6, 0.3]));
+
+  let objects = vec![
+    floor([0.8, 0.8, 0.8]),
+    platform(
+      (0.0, 1.5, -2.0),
+      (0.0, 45.0, 0.0),
+      (1.0, 1.0, 1.0),
+      [0.2, 0.4, 1.0],
+    ),
+    platform(
+      (2.0, 2.0, 2.0),
+      (20.0, 0.0, 30.0),
+      (0.5, 1.5, 0.5),
+      [0.3, 1.0, 0.3],
+    ),
+  ];
+  (game, objects)
+}
+
+#[test]
+fn lit_platforms() {
+  let (game, objects) = lit_platforms_scene();
+  assert_renders_golden("lit_platforms", &game, objects);
+}
+
+/// Only the edges of the platforms should change when they are drawn with 4x MSAA.
+#[test]
+fn msaa_edges() {
+  let (game, objects) = lit_platforms_scene();
+  let Some(single) = render(&game, objects) else {
+    return;
+  };
+  let (mut game, objects) = lit_platforms_scene();
+  game.settings.video.msaa_samples = 4;
+  let multi = render(&game, objects).unwrap();
+
+  let differing = multi
+    .pixels
+    .chunks_exact(4)
+    .zip(single.pixels.chunks_exact(4))
+    .filter(|(a, b)| {
+      a.iter()
+        .zip(b.iter())
+        .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+    })
+    .count();
+  let fraction = differing as f32 / (WIDTH * HEIGHT) as f32;
+  assert!(differing > 0, "MSAA left every edge untouched");
+  assert!(
+    fraction < 0.05,
+    "MSAA changed {:.2}% of pixels, more than the edges",
+    fraction * 100.0
+  );
+  assert_matches_golden("msaa_edges", &multi);
+}
+
+#[test]
+fn emissive_bloom() {
+  let mut game = game_state();
+  game
+    .lights
+    .push(Light::directional((0.2, -1.0, -0.3), [0.3, 0.3, 0.3]));
+
+  let mut glowing = platform(
+    (0.0, 1.5, 0.0),
+    (0.0, 0.0, 0.0),
+    (1.0, 1.0, 1.0),
+    [1.0, 0.3, 0.1],
+  );
+  glowing.material.emissive = 3.0;
+
+  assert_renders_golden(
+    "emissive_bloom",
+    &game,
+    vec![floor([0.5, 0.5, 0.5]), glowing],
+  );
+}
+
+#[test]
+fn rotated_tesseract() {
+  let mut game = lit_game_state();
+  let mut tesseract = Object4d::new(
+    Shape4d::Tesseract,
+    (0.0, 2.5, 0.0),
+    (1.0, 1.0, 1.0),
+    [0.9, 0.3, 0.8],
+  );
+  tesseract.rotation = (0.0, 30.0, 0.0).into();
+  tesseract.rotate_4d(35.0, 20.0, 0.0);
+  game.objects_4d.push(tesseract);
+
+  assert_renders_golden("rotated_tesseract", &game, vec![floor([0.5, 0.5, 0.5])]);
+}
+
+#[test]
+fn sliced_4d_objects() {
+  let mut game = lit_game_state();
+  game.camera.w = 0.4;
+
+  let mut tesseract = Object4d::new(
+    Shape4d::Tesseract,
+    (0.0, 2.5, -2.0),
+    (1.0, 1.0, 1.0),
+    [0.9, 0.3, 0.8],
+  );
+  tesseract.view = View4d::Slice;
+  tesseract.rotate_4d(30.0, 45.0, 20.0);
+  game.objects_4d.push(tesseract);
+
+  let mut skrungle = Object4d::new(
+    Shape4d::Skrungle,
+    (0.0, 0.5, 2.5),
+    (1.0, 1.0, 1.0),
+    [0.3, 0.8, 0.9],
+  );
+  skrungle.view = View4d::Slice;
+  game.objects_4d.push(skrungle);
+
+  assert_renders_golden("sliced_4d_objects", &game, vec![floor([0.5, 0.5, 0.5])]);
+}
+
+#[test]
+fn many_4d_objects() {
+  let mut game = lit_game_state();
+  // more than the 4D instance buffer starts out with, so it has to grow
+  for x in 0..8 {
+    for z in 0..5 {
+      let mut tesseract = Object4d::new(
+        Shape4d::Tesseract,
+        (x as f32 * 1.2 - 3.0, 1.0, z as f32 * 1.2 - 2.4),
+        (0.5, 0.5, 0.5),
+        [0.2 + x as f32 * 0.1, 0.3, 0.2 + z as f32 * 0.15],
+      );
+      tesseract.rotate_4d(20.0, 30.0, 0.0);
+      game.objects_4d.push(tesseract);
+    }
+  }
+
+  if let Some((actual, stats)) = render_with_stats(&game, vec![floor([0.5, 0.5, 0.5])]) {
+    assert_matches_golden("many_4d_objects", &actual);
+    // the floor in both passes and every tesseract in the main pass
+    assert_eq!(stats.instances, 2 + 40);
+  }
+}
+
+#[test]
+fn materials() {
+  let mut game = lit_game_state();
+  game
+    .lights
+    .push(Light::point((0.0, 4.0, 0.0), [1.0, 1.0, 1.0]));
+
+  let cube = |z: f32, material: Material| {
+    let mut object = platform(
+      (0.0, 1.5, z),
+      (0.0, 45.0, 0.0),
+      (1.0, 1.0, 1.0),
+      [1.0, 1.0, 1.0],
+    );
+    object.material = material;
+    object
+  };
+
+  let objects = vec![
+    floor([0.5, 0.5, 0.5]),
+    cube(
+      -3.0,
+      Material {
+        shininess: 4.0,
+        specular_strength: 0.2,
+        ..Material::new([0.9, 0.2, 0.2])
+      },
+    ),
+    cube(
+      0.0,
+      Material {
+        ambient: 0.5,
+        shininess: 128.0,
+        ..Material::new([0.2, 0.9, 0.2])
+      },
+    ),
+    cube(
+      3.0,
+      Material {
+        unlit: true,
+        ..Material::new([0.2, 0.2, 0.9])
+      },
+    ),
+  ];
+
+  assert_renders_golden("materials", &game, objects);
+}
+
+#[test]
+fn textured() {
+  let mut game = lit_game_state();
+
+  let mut floor = floor([0.8, 0.8, 0.8]);
+  floor.material.texture = TextureMapping::Triplanar;
+  floor.material.texture_scale = 0.25;
+
+  let mut cube = platform(
+    (0.0, 1.5, 0.0),
+    (0.0, 45.0, 0.0),
+    (1.0, 1.0, 1.0),
+    [1.0, 0.6, 0.2],
+  );
+  cube.material.texture = TextureMapping::Uv;
+
+  let mut tesseract = Object4d::new(
+    Shape4d::Tesseract,
+    (0.0, 1.5, -3.0),
+    (1.0, 1.0, 1.0),
+    [0.3, 0.8, 0.9],
+  );
+  tesseract.view = View4d::Slice;
+  tesseract.rotate_4d(30.0, 0.0, 20.0);
+  tesseract.material.texture = TextureMapping::Triplanar;
+  tesseract.material.texture_scale = 0.5;
+  game.objects_4d.push(tesseract);
+
+  assert_renders_golden("textured", &game, vec![floor, cube]);
+}
+
+#[test]
+fn transparency() {
+  let mut game = lit_game_state();
+
+  let glass = |position: (f32, f32, f32), color: [f32; 3], opacity: f32| {
+    let mut object = platform(position, (0.0, 45.0, 0.0), (1.0, 1.0, 1.0), color);
+    object.material.opacity = opacity;
+    object
+  };
+
+  let mut tesseract = Object4d::new(
+    Shape4d::Tesseract,
+    (2.0, 1.5, 2.5),
+    (1.0, 1.0, 1.0),
+    [0.9, 0.9, 0.2],
+  );
+  tesseract.view = View4d::Slice;
+  tesseract.rotate_4d(30.0, 0.0, 20.0);
+  tesseract.material.opacity = 0.5;
+  game.objects_4d.push(tesseract);
+
+  let objects = vec![
+    floor([0.5, 0.5, 0.5]),
+    platform(
+      (4.0, 1.5, 0.0),
+      (0.0, 0.0, 0.0),
+      (0.5, 1.0, 3.0),
+      [0.2, 0.4, 1.0],
+    ),
+    // listed front to back, the renderer has to sort them
+    glass((-2.0, 1.5, -0.5), [1.0, 0.2, 0.2], 0.4),
+    glass((1.0, 1.5, -1.0), [0.2, 1.0, 0.2], 0.6),
+  ];
+
+  assert_renders_golden("transparency", &game, objects);
+}
+
+#[test]
+fn outlines() {
+  let game = lit_game_state();
+
+  let mut selected = platform(
+    (0.0, 1.5, -2.0),
+    (0.0, 45.0, 0.0),
+    (1.0, 1.0, 1.0),
+    [0.2, 0.4, 1.0],
+  );
+  selected.highlight = Highlight::Selected;
+
+  let mut hovered = platform(
+    (0.0, 1.0, 2.0),
+    (0.0, 0.0, 0.0),
+    (3.0, 0.25, 1.0),
+    [0.2, 0.8, 0.3],
+  );
+  hovered.highlight = Highlight::Hovered;
+
+  let mut glass = platform(
+    (-2.0, 1.5, 1.0),
+    (0.0, 0.0, 0.0),
+    (0.75, 0.75, 0.75),
+    [1.0, 0.2, 0.2],
+  );
+  glass.material.opacity = 0.4;
+  glass.highlight = Highlight::Selected;
+
+  let objects = vec![floor([0.5, 0.5, 0.5]), selected, hovered, glass];
+  assert_renders_golden("outlines", &game, objects);
+}
+
+fn sky_scene() -> (GameState, Vec<GameObject>) {
+  let mut game = lit_game_state();
+  // looking up at the horizon so most of the frame is sky
+  game.camera = Camera::new((-8.0, 3.0, 0.0), cgmath::Deg(0.0), cgmath::Deg(10.0));
+
+  let mut glass = platform(
+    (0.0, 3.5, 2.0),
+    (0.0, 45.0, 0.0),
+    (1.0, 1.0, 1.0),
+    [0.2, 0.4, 1.0],
+  );
+  glass.material.opacity = 0.5;
+
+  let objects = vec![
+    floor([0.5, 0.5, 0.5]),
+    platform(
+      (0.0, 3.5, -2.0),
+      (0.0, 45.0, 0.0),
+      (1.0, 1.0, 1.0),
+      [0.9, 0.3, 0.2],
+    ),
+    glass,
+  ];
+  (game, objects)
+}
+
+#[test]
+fn gradient_sky() {
+  let (mut game, objects) = sky_scene();
+  game.sky = Sky::Gradient(SkyGradient {
+    zenith: [0.01, 0.01, 0.05],
+    horizon: [0.2, 0.1, 0.3],
+    ground: [0.02, 0.02, 0.02],
+    stars: 0.05,
+  });
+
+  assert_renders_golden("gradient_sky", &game, objects);
+}
+
+#[test]
+fn cubemap_sky() {
+  let (mut game, objects) = sky_scene();
+  game.sky = Sky::Cubemap("sky".to_string());
+
+  assert_renders_golden("cubemap_sky", &game, objects);
+}
+
+#[test]
+fn lod_batches() {
+  let mut game = lit_game_state();
+  game.camera = Camera::new((-8.0, 4.0, 0.0), cgmath::Deg(0.0), cgmath::Deg(-10.0));
+
+  let sphere = |position, color| {
+    let mut sphere = platform(position, (0.0, 0.0, 0.0), (0.8, 0.8, 0.8), color);
+    sphere.shape = Shape3d::Sphere;
+    sphere
+  };
+
+  let mut objects = vec![
+    platform(
+      (30.0, 0.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (45.0, 0.5, 15.0),
+      [0.5, 0.5, 0.5],
+    ),
+    // behind the camera, only drawn into the shadow map
+    platform(
+      (-30.0, 1.0, 0.0),
+      (0.0, 0.0, 0.0),
+      (1.0, 1.0, 1.0),
+      [0.5, 0.5, 0.5],
+    ),
+  ];
+  // one row of spheres per LOD, interleaved so the queue has to sort them into batches
+  for z in [-3.0, 0.0, 3.0] {
+    for (x, color) in [
+      (0.0, [0.9, 0.3, 0.2]),
+      (25.0, [0.2, 0.8, 0.3]),
+      (60.0, [0.2, 0.4, 1.0]),
+    ] {
+      objects.push(sphere((x, 1.3, z), color));
+    }
+  }
+  objects[2].highlight = Highlight::Selected;
+
+  let mut glass = sphere((-2.0, 1.5, 1.5), [1.0, 1.0, 1.0]);
+  glass.material.opacity = 0.4;
+  objects.push(glass);
+
+  if let Some((actual, stats)) = render_with_stats(&game, objects) {
+    assert_matches_golden("lod_batches", &actual);
+    // a cube batch and three sphere LODs in both passes, the culled cube in the shadow pass and
+    // the glass sphere in the transparent pass
+    assert_eq!(stats.draw_calls, 10);
+    assert_eq!(stats.instances, 22);
+    assert_eq!(stats.culled, 1);
+  }
+}